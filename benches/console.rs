@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use ftail::{channels::console::ConsoleLogger, Config};
+use log::{Level, Log, Record};
+use std::{sync::Arc, thread};
+
+// Run with stdout redirected, e.g. `cargo bench --bench console > bench_output.txt`.
+
+const THREADS: usize = 8;
+const RECORDS_PER_THREAD: usize = 1000;
+
+fn log_from_threads(logger: &Arc<ConsoleLogger>) {
+    let handles = (0..THREADS)
+        .map(|_| {
+            let logger = Arc::clone(logger);
+
+            thread::spawn(move || {
+                for i in 0..RECORDS_PER_THREAD {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("This is message {}", i))
+                            .level(Level::Info)
+                            .target("bench")
+                            .build(),
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    logger.flush();
+}
+
+fn console(c: &mut Criterion) {
+    let mut group = c.benchmark_group("console");
+
+    group.throughput(Throughput::Elements((THREADS * RECORDS_PER_THREAD) as u64));
+    group.sample_size(10);
+
+    let unbuffered = Arc::new(ConsoleLogger::new(Config::default()));
+
+    group.bench_function("unbuffered", |b| b.iter(|| log_from_threads(&unbuffered)));
+
+    let batched = Arc::new(ConsoleLogger::new(Config {
+        console_batch_size: Some(64),
+        ..Default::default()
+    }));
+
+    group.bench_function("batched", |b| b.iter(|| log_from_threads(&batched)));
+
+    group.finish();
+}
+
+criterion_group!(benches, console);
+criterion_main!(benches);
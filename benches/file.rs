@@ -0,0 +1,81 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use ftail::{
+    channels::{daily_file::DailyFileLogger, single_file::SingleFileLogger},
+    Config,
+};
+use log::{Level, Log, Record};
+use std::{sync::Arc, thread};
+
+const THREADS: usize = 8;
+const RECORDS_PER_THREAD: usize = 1000;
+
+fn log_from_threads<L: Log + 'static>(logger: &Arc<L>) {
+    let handles = (0..THREADS)
+        .map(|_| {
+            let logger = Arc::clone(logger);
+
+            thread::spawn(move || {
+                for i in 0..RECORDS_PER_THREAD {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("This is message {}", i))
+                            .level(Level::Info)
+                            .target("bench")
+                            .build(),
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    logger.flush();
+}
+
+fn file(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("ftail-bench-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("daily")).unwrap();
+
+    let mut group = c.benchmark_group("file");
+
+    group.throughput(Throughput::Elements((THREADS * RECORDS_PER_THREAD) as u64));
+    group.sample_size(10);
+
+    let single_file = Arc::new(
+        SingleFileLogger::new(
+            dir.join("single.log").to_str().unwrap(),
+            false,
+            Config {
+                max_file_size: Some(1024 * 1024 * 1024),
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+
+    group.bench_function("single_file", |b| b.iter(|| log_from_threads(&single_file)));
+
+    let daily_file = Arc::new(
+        DailyFileLogger::new(
+            dir.join("daily").to_str().unwrap(),
+            Config {
+                max_file_size: Some(1024 * 1024 * 1024),
+                retention_days: Some(30),
+                ..Default::default()
+            },
+        )
+        .unwrap(),
+    );
+
+    group.bench_function("daily_file", |b| b.iter(|| log_from_threads(&daily_file)));
+
+    group.finish();
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+criterion_group!(benches, file);
+criterion_main!(benches);
@@ -31,10 +31,6 @@ struct CustomLogger {
 
 impl Log for CustomLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
         metadata.level() <= self.config.level_filter
     }
 
@@ -61,6 +61,7 @@ pub struct Style<T> {
 
 generate_styling_functions! {
     #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     #[allow(non_camel_case_types)]
     pub enum StyleCode {
         #[code = 30]
@@ -158,6 +159,26 @@ impl<T: Display> Display for Style<T> {
     }
 }
 
+/// Remove the escape codes added by the styling functions from the text.
+pub fn strip(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("\x1b[") {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        match rest.find('m') {
+            Some(end) => rest = &rest[end + 1..],
+            None => break,
+        }
+    }
+
+    result.push_str(rest);
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
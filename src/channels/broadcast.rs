@@ -0,0 +1,73 @@
+use log::Log;
+use std::sync::{
+    mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+    Arc, Mutex,
+};
+
+use crate::{Config, RenderedRecord};
+
+/// A fan-out of rendered records to any number of subscribers, e.g. the websocket connections of
+/// a live log viewer. Subscribers only receive the records logged after they subscribed, and a
+/// subscriber whose queue is full misses records instead of blocking the logger.
+#[derive(Clone)]
+pub struct Broadcast {
+    subscribers: Arc<Mutex<Vec<SyncSender<RenderedRecord>>>>,
+    capacity: usize,
+}
+
+impl Broadcast {
+    /// Create a broadcast that queues up to `capacity` records for each subscriber.
+    pub fn new(capacity: usize) -> Self {
+        Broadcast {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Subscribe to the records logged from now on. Dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> Receiver<RenderedRecord> {
+        let (sender, receiver) = sync_channel(self.capacity);
+
+        self.subscribers.lock().unwrap().push(sender);
+
+        receiver
+    }
+
+    fn send(&self, record: RenderedRecord) {
+        self.subscribers.lock().unwrap().retain(|subscriber| {
+            match subscriber.try_send(record.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+/// A logger that sends rendered records to the subscribers of a broadcast.
+pub struct BroadcastLogger {
+    broadcast: Broadcast,
+    config: Config,
+}
+
+impl BroadcastLogger {
+    pub fn new(broadcast: Broadcast, config: Config) -> Self {
+        BroadcastLogger { broadcast, config }
+    }
+}
+
+impl Log for BroadcastLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.broadcast
+            .send(RenderedRecord::new(record, &self.config));
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,36 @@
+use log::Log;
+use std::sync::Arc;
+
+use crate::Config;
+
+/// The closure called by the callback channel for each record.
+pub type RecordCallback = Arc<dyn Fn(&log::Record, &Config) + Send + Sync>;
+
+/// A logger that calls a closure for each record, e.g. for an ad-hoc integration that does not
+/// need a whole `Log` implementation.
+pub struct CallbackLogger {
+    callback: RecordCallback,
+    config: Config,
+}
+
+impl CallbackLogger {
+    pub fn new(callback: RecordCallback, config: Config) -> Self {
+        CallbackLogger { callback, config }
+    }
+}
+
+impl Log for CallbackLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        (self.callback)(record, &self.config);
+    }
+
+    fn flush(&self) {}
+}
@@ -1,7 +1,8 @@
-use log::{LevelFilter, Log};
+use log::Log;
 
 use crate::{
-    formatters::{default::DefaultFormatter, Formatter},
+    formatters::default::DefaultFormatter,
+    helpers::{flush_console_lines, format_message, prefix_terminal_title, print_console_line},
     Config,
 };
 
@@ -18,10 +19,6 @@ impl ConsoleLogger {
 
 impl Log for ConsoleLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
         metadata.level() <= self.config.level_filter
     }
 
@@ -30,10 +27,17 @@ impl Log for ConsoleLogger {
             return;
         }
 
-        let formatter = DefaultFormatter::new(record, &self.config);
+        let formatter = DefaultFormatter::new(record, &self.config).with_wrap(true);
+
+        let mut line = format_message(record, &self.config, formatter);
+        prefix_terminal_title(&mut line, record, &self.config);
 
-        println!("{}", formatter.format());
+        print_console_line(line, record.level(), &self.config);
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if self.config.console_batch_size.is_some() {
+            flush_console_lines();
+        }
+    }
 }
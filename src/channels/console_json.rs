@@ -0,0 +1,74 @@
+use log::Log;
+
+use crate::{
+    formatters::json::JsonFormatter,
+    helpers::{flush_console_lines, format_message, print_console_line},
+    Config,
+};
+
+/// A logger that logs messages to the console as newline-delimited JSON.
+pub struct JsonConsoleLogger {
+    pretty: bool,
+    config: Config,
+}
+
+impl JsonConsoleLogger {
+    pub fn new(config: Config) -> Self {
+        JsonConsoleLogger {
+            pretty: false,
+            config,
+        }
+    }
+
+    /// Log indented multi-line JSON instead, for reading during development. The output is not
+    /// newline-delimited, so it is not suitable for machine ingestion.
+    #[cfg(feature = "pretty-json")]
+    pub fn pretty(config: Config) -> Self {
+        JsonConsoleLogger {
+            pretty: true,
+            config,
+        }
+    }
+}
+
+impl Log for JsonConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = JsonFormatter::new(record, &self.config);
+
+        let mut line = format_message(record, &self.config, formatter);
+
+        if self.pretty {
+            line = pretty(line);
+        }
+
+        print_console_line(line, record.level(), &self.config);
+    }
+
+    fn flush(&self) {
+        if self.config.console_batch_size.is_some() {
+            flush_console_lines();
+        }
+    }
+}
+
+/// Indent the JSON of the line, keeping the order of the fields. A line that is not JSON, e.g.
+/// after a `message_transform`, is kept as is.
+#[cfg(feature = "pretty-json")]
+pub(crate) fn pretty(line: String) -> String {
+    serde_json::from_str::<serde_json::Value>(&line)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or(line)
+}
+
+#[cfg(not(feature = "pretty-json"))]
+fn pretty(line: String) -> String {
+    line
+}
@@ -0,0 +1,44 @@
+use log::Log;
+
+use crate::{
+    formatters::logfmt::LogfmtFormatter,
+    helpers::{flush_console_lines, format_message, print_console_line},
+    Config,
+};
+
+/// A logger that logs messages to the console as logfmt.
+pub struct LogfmtConsoleLogger {
+    config: Config,
+}
+
+impl LogfmtConsoleLogger {
+    pub fn new(config: Config) -> Self {
+        LogfmtConsoleLogger { config }
+    }
+}
+
+impl Log for LogfmtConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = LogfmtFormatter::new(record, &self.config);
+
+        print_console_line(
+            format_message(record, &self.config, formatter),
+            record.level(),
+            &self.config,
+        );
+    }
+
+    fn flush(&self) {
+        if self.config.console_batch_size.is_some() {
+            flush_console_lines();
+        }
+    }
+}
@@ -1,31 +1,72 @@
-use log::{LevelFilter, Log};
+use log::Log;
 use std::{
     fs::File,
-    io::{LineWriter, Write},
-    path::PathBuf,
-    sync::Mutex,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc, Mutex},
+};
+
+use chrono::{
+    format::{Item, Parsed, StrftimeItems},
+    NaiveDate,
 };
 
 use crate::{
     error::FtailError,
-    formatters::{default::DefaultFormatter, Formatter},
-    helpers::rotate_if_exceeds_max_file_size,
-    Config,
+    formatters::json::JsonFormatter,
+    helpers::{
+        append_backtrace, format_file_message, format_message, line_size, lock, should_flush,
+        track_write, write_footer, write_line, CurrentFile, LineNumbers, RecordCounter,
+    },
+    writer::format_datetime,
+    Config, WriteErrors,
 };
 
-/// A logger that logs messages to a daily log file.
+/// The file names of the daily file channel.
+pub(crate) const DAILY_PATTERN: &str = "%Y-%m-%d.log";
+
+/// The file names of the hourly file channel.
+pub(crate) const HOURLY_PATTERN: &str = "%Y-%m-%d-%H.log";
+
+/// A logger that logs messages to a daily log file. When the file exceeds `max_file_size`, the
+/// logger continues in the next file of the day: `2024-09-13.log`, `2024-09-13.1.log`, ...
+/// The names of the files are formatted with a chrono pattern, and the logger starts a new file
+/// whenever the formatted name changes, e.g. every hour for the hourly file channel.
 pub struct DailyFileLogger {
-    file: Mutex<LineWriter<File>>,
-    file_path: PathBuf,
+    file: Mutex<DailyFile>,
+    current: Arc<CurrentFile>,
+    line_numbers: LineNumbers,
+    counter: RecordCounter,
     dir: String,
-    current_date: Mutex<String>,
+    pattern: String,
+    json: bool,
     config: Config,
+    write_errors: WriteErrors,
+}
+
+/// The current file with its name and index, replaced together under the lock of the logger.
+struct DailyFile {
+    writer: BufWriter<File>,
+    name: String,
+    index: u32,
 }
 
 impl DailyFileLogger {
     pub fn new(dir: &str, config: Config) -> Result<Self, FtailError> {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-        let path = format!("{}/{}.log", dir, today);
+        Self::with_pattern(dir, DAILY_PATTERN, config)
+    }
+
+    /// Name the files with a chrono pattern, e.g. `app-%Y-%m-%d.log`. The rest of the pattern is
+    /// kept as is.
+    pub fn with_pattern(dir: &str, pattern: &str, config: Config) -> Result<Self, FtailError> {
+        if StrftimeItems::new(pattern).any(|item| item == Item::Error) {
+            return Err(FtailError::InvalidDatetimeFormat(pattern.to_string()));
+        }
+
+        let now = (config.clock)();
+        let name = now.format(pattern).to_string();
+        let index = last_index(dir, &name);
+        let path = file_path(dir, &name, index);
 
         let file = std::fs::OpenOptions::new()
             .create(true)
@@ -39,46 +80,119 @@ impl DailyFileLogger {
             return Err(FtailError::PermissionsError(dir.to_string()));
         }
 
+        let mut size = file.metadata().map_err(FtailError::IoError)?.len();
+
+        let mut file = BufWriter::new(file);
+
+        if config.session_separator && size > 0 {
+            let separator = format!("{} --- new session ---", format_datetime(&config));
+
+            write_line(&mut file, &separator, &config).map_err(FtailError::IoError)?;
+            file.flush().map_err(FtailError::IoError)?;
+            size += line_size(&separator, &config);
+        }
+
+        if let Some(retention_days) = config.retention_days {
+            remove_old_log_files(dir, pattern, retention_days, now.date_naive());
+        }
+
         Ok(DailyFileLogger {
-            file: Mutex::new(LineWriter::new(file)),
-            file_path: PathBuf::from(path),
+            file: Mutex::new(DailyFile {
+                writer: file,
+                name,
+                index,
+            }),
+            current: CurrentFile::new(Some(path), size),
+            line_numbers: LineNumbers::new(&config),
+            counter: RecordCounter::new(),
             dir: dir.to_string(),
-            current_date: Mutex::new(today),
+            pattern: pattern.to_string(),
+            json: false,
             config,
+            write_errors: WriteErrors::current(),
         })
     }
 
-    fn rotate_daily_file(&self) {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-        let mut current_date = self.current_date.lock().unwrap();
+    /// Log newline-delimited JSON, one object per message.
+    pub fn json(dir: &str, config: Config) -> Result<Self, FtailError> {
+        let mut logger = Self::new(dir, config)?;
+        logger.json = true;
 
-        if *current_date != today {
-            let path = format!("{}/{}.log", self.dir, today);
+        Ok(logger)
+    }
 
-            let new_file = std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(path)
-                .unwrap();
+    /// Share the path and size of the current file through `current`, e.g. to query them from the
+    /// handle.
+    pub(crate) fn with_current_file(mut self, current: Arc<CurrentFile>) -> Self {
+        current.replace(&self.current);
+        self.current = current;
+
+        self
+    }
 
-            let mut file = self.file.lock().unwrap();
+    /// Continue in a new file when the formatted name changed, e.g. the next day started, and
+    /// in the next file of the period when the current one exceeds `max_file_size` or the handle
+    /// requested it with `rotate_now`. Called under the lock of the file, so the checks and the
+    /// switch are never interleaved with another rotation or a write. Returns whether a new
+    /// period started.
+    fn rotate(&self, file: &mut DailyFile) -> bool {
+        let name = (self.config.clock)().format(&self.pattern).to_string();
+        let requested = self.current.rotate.swap(false, Ordering::AcqRel);
 
-            *file = LineWriter::new(new_file);
-            *current_date = today;
+        if file.name != name {
+            let index = last_index(&self.dir, &name);
+
+            // keep writing to the current file when the new one cannot be opened, e.g. the
+            // directory was removed, and try again on the next record
+            return track_write(self.switch_to(file, name, index), &self.write_errors);
+        }
+
+        let exceeds = self
+            .config
+            .max_file_size
+            .is_some_and(|max_file_size| self.current.size.load(Ordering::Acquire) > max_file_size);
+
+        if exceeds || requested {
+            let index = file.index + 1;
+
+            track_write(self.switch_to(file, name, index), &self.write_errors);
         }
 
+        false
+    }
+
+    /// Flush the current file and continue in the file with the name and index.
+    fn switch_to(&self, file: &mut DailyFile, name: String, index: u32) -> std::io::Result<()> {
+        let path = file_path(&self.dir, &name, index);
+
+        // the records of the current file are written before any record of the new one
+        track_write(file.writer.flush(), &self.write_errors);
+
+        let (new_file, size) = open_file(&path)?;
+
+        self.current.size.store(size, Ordering::Release);
+        *self.current.path.write().unwrap() = Some(path);
+        *file = DailyFile {
+            writer: BufWriter::new(new_file),
+            name,
+            index,
+        };
+        self.line_numbers.reset();
+
+        Ok(())
+    }
+
+    fn remove_old_log_files(&self) {
         if let Some(retention_days) = self.config.retention_days {
-            remove_old_log_files(&self.dir, retention_days);
+            let today = (self.config.clock)().date_naive();
+
+            remove_old_log_files(&self.dir, &self.pattern, retention_days, today);
         }
     }
 }
 
 impl Log for DailyFileLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
         metadata.level() <= self.config.level_filter
     }
 
@@ -87,38 +201,179 @@ impl Log for DailyFileLogger {
             return;
         }
 
-        rotate_if_exceeds_max_file_size(&self.file, self.file_path.clone(), &self.config);
-        self.rotate_daily_file();
+        let mut new_period = false;
+
+        let (mut file, message, line_no) = self.line_numbers.lock_rotate_and_format(
+            &self.file,
+            |file| new_period = self.rotate(file),
+            |line_no| {
+                let mut message = if self.json {
+                    format_message(
+                        record,
+                        &self.config,
+                        JsonFormatter::new(record, &self.config),
+                    )
+                } else {
+                    format_file_message(record, &self.config, line_no)
+                };
+                append_backtrace(&mut message, record.level(), &self.config);
+
+                message
+            },
+        );
+
+        if track_write(
+            write_line(&mut file.writer, &message, &self.config),
+            &self.write_errors,
+        ) {
+            self.line_numbers.written(line_no);
+        }
+        self.current
+            .size
+            .fetch_add(line_size(&message, &self.config), Ordering::AcqRel);
+
+        let records = self.counter.count(record.level());
 
-        let formatter = DefaultFormatter::new(record, &self.config);
+        if should_flush(records, record.level(), &self.config) {
+            track_write(file.writer.flush(), &self.write_errors);
+        }
 
-        let mut file = self.file.lock().unwrap();
-        writeln!(file, "{}", formatter.format()).unwrap();
-        file.flush().unwrap();
+        drop(file);
+
+        if new_period {
+            self.remove_old_log_files();
+        }
     }
 
     fn flush(&self) {
-        self.file.lock().unwrap().flush().unwrap();
+        let mut file = lock(&self.file);
+
+        let new_period = self.current.rotate.load(Ordering::Acquire) && self.rotate(&mut file);
+        track_write(file.writer.flush(), &self.write_errors);
+
+        drop(file);
+
+        if new_period {
+            self.remove_old_log_files();
+        }
+    }
+}
+
+impl Drop for DailyFileLogger {
+    fn drop(&mut self) {
+        if let Ok(file) = self.file.get_mut() {
+            write_footer(
+                &mut file.writer,
+                &self.counter,
+                &self.config,
+                &self.write_errors,
+            );
+        }
     }
 }
 
-fn remove_old_log_files(dir: &str, retention_days: u64) {
-    let files = std::fs::read_dir(dir).unwrap();
+/// Open a file to append to, with its current size.
+fn open_file(path: &Path) -> std::io::Result<(File, u64)> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let size = file.metadata()?.len();
+
+    Ok((file, size))
+}
+
+/// The path of a file of the period, with the index before the extension after the first file:
+/// `2024-09-13.log`, `2024-09-13.1.log`, ...
+fn file_path(dir: &str, name: &str, index: u32) -> PathBuf {
+    let (stem, extension) = split_extension(name);
 
-    for file in files {
-        let file = file.unwrap();
-        let path = file.path();
+    match index {
+        0 => PathBuf::from(format!("{}/{}", dir, name)),
+        _ => PathBuf::from(format!("{}/{}.{}{}", dir, stem, index, extension)),
+    }
+}
 
-        if path.extension().and_then(|ext| ext.to_str()) == Some("log") {
-            let metadata = file.metadata().unwrap();
-            let modified_system_time = metadata.modified().unwrap();
-            let modified = chrono::DateTime::<chrono::Local>::from(modified_system_time);
-            let now = chrono::Local::now();
-            let duration = now.signed_duration_since(modified);
+/// Split a file name before its extension, e.g. `2024-09-13` and `.log`.
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(dot) => name.split_at(dot),
+        None => (name, ""),
+    }
+}
 
-            if duration.num_days() > retention_days as i64 {
-                std::fs::remove_file(path).unwrap();
+/// The index of the last file of the period in the directory, `0` for `2024-09-13.log`.
+fn last_index(dir: &str, name: &str) -> u32 {
+    let Ok(files) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let (stem, extension) = split_extension(name);
+    let prefix = format!("{}.", stem);
+
+    files
+        .flatten()
+        .filter_map(|file| {
+            let name = file.file_name().to_str()?.to_string();
+
+            name.strip_prefix(&prefix)?
+                .strip_suffix(extension)?
+                .parse::<u32>()
+                .ok()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// The date of a file named with the pattern, with or without the index of the file.
+fn parse_date(name: &str, pattern: &str) -> Option<NaiveDate> {
+    let parts = name.split('.').collect::<Vec<&str>>();
+
+    let unindexed = (1..parts.len())
+        .filter(|&i| parts[i].parse::<u32>().is_ok())
+        .map(|i| [&parts[..i], &parts[i + 1..]].concat().join("."));
+
+    std::iter::once(name.to_string())
+        .chain(unindexed)
+        .find_map(|name| {
+            let mut parsed = Parsed::new();
+            chrono::format::parse(&mut parsed, &name, StrftimeItems::new(pattern)).ok()?;
+
+            parsed.to_naive_date().ok()
+        })
+}
+
+/// Remove the log files older than `retention_days`. The age of a file is taken from its name when
+/// it matches the pattern, other files with the extension of the pattern fall back to their
+/// modification time.
+fn remove_old_log_files(dir: &str, pattern: &str, retention_days: u64, today: NaiveDate) {
+    let Ok(files) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let (_, extension) = split_extension(pattern);
+
+    for file in files.flatten() {
+        let Some(name) = file.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+
+        let date = parse_date(&name, pattern).or_else(|| {
+            if extension.is_empty() || !name.ends_with(extension) {
+                return None;
             }
+
+            let modified = file.metadata().ok()?.modified().ok()?;
+
+            Some(chrono::DateTime::<chrono::Local>::from(modified).date_naive())
+        });
+
+        let Some(date) = date else {
+            continue;
+        };
+
+        if today.signed_duration_since(date).num_days() > retention_days as i64 {
+            let _ = std::fs::remove_file(file.path());
         }
     }
 }
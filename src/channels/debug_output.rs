@@ -0,0 +1,44 @@
+use log::Log;
+
+use crate::{formatters::default::DefaultFormatter, helpers::format_message, Config};
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn OutputDebugStringW(output_string: *const u16);
+}
+
+/// A logger that sends messages to the attached debugger with `OutputDebugStringW`.
+pub struct DebugOutputLogger {
+    config: Config,
+}
+
+impl DebugOutputLogger {
+    pub fn new(config: Config) -> Self {
+        DebugOutputLogger { config }
+    }
+}
+
+impl Log for DebugOutputLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = DefaultFormatter::new(record, &self.config);
+        let message = format_message(record, &self.config, formatter);
+
+        let wide = message
+            .encode_utf16()
+            .chain("\r\n\0".encode_utf16())
+            .collect::<Vec<u16>>();
+
+        // the string is null-terminated and outlives the call
+        unsafe { OutputDebugStringW(wide.as_ptr()) };
+    }
+
+    fn flush(&self) {}
+}
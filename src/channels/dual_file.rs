@@ -0,0 +1,129 @@
+use log::Log;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    sync::Mutex,
+};
+
+use crate::{
+    ansi_escape,
+    error::FtailError,
+    formatters::readable::ReadableFormatter,
+    helpers::{
+        append_backtrace, format_message, lock, should_flush, track_write, write_footer,
+        write_line, RecordCounter,
+    },
+    Config, WriteErrors,
+};
+
+/// A logger that logs formatted messages to two files, with the colors in one and without them in
+/// the other. Each record is formatted once and the colors are stripped for the plain copy.
+pub struct DualFileLogger {
+    files: Mutex<DualWriter>,
+    counter: RecordCounter,
+    config: Config,
+    write_errors: WriteErrors,
+}
+
+struct DualWriter {
+    colored: BufWriter<File>,
+    plain: BufWriter<File>,
+}
+
+impl DualFileLogger {
+    pub fn new(colored_path: &str, plain_path: &str, config: Config) -> Result<Self, FtailError> {
+        Ok(DualFileLogger {
+            files: Mutex::new(DualWriter {
+                colored: BufWriter::new(open(colored_path)?),
+                plain: BufWriter::new(open(plain_path)?),
+            }),
+            counter: RecordCounter::new(),
+            config,
+            write_errors: WriteErrors::current(),
+        })
+    }
+}
+
+fn open(path: &str) -> Result<File, FtailError> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(FtailError::IoError)?;
+
+    if file
+        .metadata()
+        .map_err(FtailError::IoError)?
+        .permissions()
+        .readonly()
+    {
+        return Err(FtailError::PermissionsError(path.to_string()));
+    }
+
+    Ok(file)
+}
+
+impl Log for DualFileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = ReadableFormatter::new(record, &self.config);
+
+        let mut message = format_message(record, &self.config, formatter);
+        append_backtrace(&mut message, record.level(), &self.config);
+
+        let plain = ansi_escape::strip(&message);
+
+        let mut files = lock(&self.files);
+        track_write(
+            write_line(&mut files.colored, &message, &self.config),
+            &self.write_errors,
+        );
+        track_write(
+            write_line(&mut files.plain, &plain, &self.config),
+            &self.write_errors,
+        );
+
+        let records = self.counter.count(record.level());
+
+        if should_flush(records, record.level(), &self.config) {
+            files.flush(&self.write_errors);
+        }
+    }
+
+    fn flush(&self) {
+        lock(&self.files).flush(&self.write_errors);
+    }
+}
+
+impl DualWriter {
+    fn flush(&mut self, write_errors: &WriteErrors) {
+        track_write(self.colored.flush(), write_errors);
+        track_write(self.plain.flush(), write_errors);
+    }
+}
+
+impl Drop for DualFileLogger {
+    fn drop(&mut self) {
+        if let Ok(files) = self.files.get_mut() {
+            write_footer(
+                &mut files.colored,
+                &self.counter,
+                &self.config,
+                &self.write_errors,
+            );
+            write_footer(
+                &mut files.plain,
+                &self.counter,
+                &self.config,
+                &self.write_errors,
+            );
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use log::Log;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{error::FtailError, formatters::json::escape, writer::LogWriter, Config, WriteErrors};
+
+const MAX_BATCH_SIZE: usize = 500;
+const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A logger that indexes the records in Elasticsearch with the `_bulk` API. The records are sent
+/// by a background thread in batches, retrying with the `retry_policy` when the node is
+/// unavailable and retrying the items it rejects with `429` or `5xx`.
+pub struct ElasticsearchLogger {
+    sender: Option<Sender<Message>>,
+    worker: Option<JoinHandle<()>>,
+    config: Config,
+}
+
+enum Message {
+    Document(String),
+    Flush(Sender<()>),
+}
+
+/// The `_bulk` endpoint of an index, on a node reached over plain HTTP.
+struct Endpoint {
+    host: String,
+    path: String,
+    authorization: Option<String>,
+}
+
+impl ElasticsearchLogger {
+    pub fn new(url: &str, index: &str, config: Config) -> Result<Self, FtailError> {
+        let endpoint = Endpoint::new(url, index)?;
+
+        let (sender, receiver) = channel();
+        let worker_config = config.clone();
+        let write_errors = WriteErrors::current();
+
+        let worker = std::thread::Builder::new()
+            .name("ftail-elasticsearch".to_string())
+            .spawn(move || run(endpoint, receiver, worker_config, write_errors))
+            .map_err(FtailError::IoError)?;
+
+        Ok(ElasticsearchLogger {
+            sender: Some(sender),
+            worker: Some(worker),
+            config,
+        })
+    }
+
+    fn document(&self, record: &log::Record) -> String {
+        let writer = LogWriter::new(record, &self.config);
+        let timestamp = (self.config.clock)()
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+        let mut document = format!(
+            "{{\"@timestamp\":\"{}\",\"level\":\"{}\",\"target\":\"{}\",\"message\":\"{}\"",
+            timestamp,
+            record.level(),
+            escape(&writer.get_target()),
+            escape(&writer.get_args())
+        );
+
+        if let Some(file) = writer.get_file() {
+            document.push_str(&format!(",\"file\":\"{}\"", escape(&file)));
+        }
+
+        if let Some(line) = writer.get_line() {
+            document.push_str(&format!(",\"line\":{}", line));
+        }
+
+        document.push('}');
+
+        document
+    }
+}
+
+fn run(endpoint: Endpoint, receiver: Receiver<Message>, config: Config, write_errors: WriteErrors) {
+    let mut documents = Vec::new();
+    let mut first_at = Instant::now();
+
+    loop {
+        let message = if documents.is_empty() {
+            receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            receiver.recv_timeout(BATCH_INTERVAL.saturating_sub(first_at.elapsed()))
+        };
+
+        match message {
+            Ok(Message::Document(document)) => {
+                if documents.is_empty() {
+                    first_at = Instant::now();
+                }
+
+                documents.push(document);
+
+                if documents.len() >= MAX_BATCH_SIZE {
+                    send_batch(
+                        &endpoint,
+                        std::mem::take(&mut documents),
+                        &config,
+                        &write_errors,
+                    );
+                }
+            }
+            Ok(Message::Flush(done)) => {
+                send_batch(
+                    &endpoint,
+                    std::mem::take(&mut documents),
+                    &config,
+                    &write_errors,
+                );
+                let _ = done.send(());
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                send_batch(
+                    &endpoint,
+                    std::mem::take(&mut documents),
+                    &config,
+                    &write_errors,
+                );
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                send_batch(
+                    &endpoint,
+                    std::mem::take(&mut documents),
+                    &config,
+                    &write_errors,
+                );
+
+                return;
+            }
+        }
+    }
+}
+
+/// Send the documents, retrying the whole batch when the request fails and the items rejected
+/// with a retryable status. The documents that are given up on are counted as failed writes.
+fn send_batch(
+    endpoint: &Endpoint,
+    mut documents: Vec<String>,
+    config: &Config,
+    write_errors: &WriteErrors,
+) {
+    let mut last_error = None;
+
+    for attempt in 1..=config.retry_policy.max_attempts.max(1) {
+        if documents.is_empty() {
+            return;
+        }
+
+        if attempt > 1 {
+            std::thread::sleep(config.retry_policy.delay(attempt - 1));
+        }
+
+        match endpoint.post(&bulk_body(&documents)) {
+            Ok((status, response)) if status < 300 => {
+                let statuses = item_statuses(&response);
+
+                documents = documents
+                    .into_iter()
+                    .zip(statuses.into_iter().chain(std::iter::repeat(200)))
+                    .filter_map(|(document, status)| match status {
+                        ..300 => None,
+                        429 | 500.. => Some(document),
+                        _ => {
+                            write_errors.push(std::io::Error::other(format!(
+                                "Elasticsearch rejected a document with status {}",
+                                status
+                            )));
+
+                            None
+                        }
+                    })
+                    .collect();
+
+                last_error = Some(std::io::Error::other(
+                    "Elasticsearch kept rejecting documents with a retryable status",
+                ));
+            }
+            Ok((status, response)) if status != 429 && status < 500 => {
+                write_errors.push(std::io::Error::other(format!(
+                    "Elasticsearch rejected the batch with status {}: {}",
+                    status, response
+                )));
+
+                return;
+            }
+            Ok((status, _)) => {
+                last_error = Some(std::io::Error::other(format!(
+                    "Elasticsearch is unavailable, status {}",
+                    status
+                )));
+            }
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    if let (false, Some(error)) = (documents.is_empty(), last_error) {
+        write_errors.push(error);
+    }
+}
+
+fn bulk_body(documents: &[String]) -> String {
+    let mut body = String::new();
+
+    for document in documents {
+        body.push_str("{\"create\":{}}\n");
+        body.push_str(document);
+        body.push('\n');
+    }
+
+    body
+}
+
+/// The status of each item of a `_bulk` response, in the order of the documents. The items only
+/// have a `status` key at their top level, so the keys are collected in order.
+fn item_statuses(response: &str) -> Vec<u16> {
+    let Some(start) = response.find("\"items\"") else {
+        return Vec::new();
+    };
+
+    response[start..]
+        .split("\"status\":")
+        .skip(1)
+        .filter_map(|rest| {
+            let digits = rest.trim_start();
+            let end = digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len());
+
+            digits[..end].parse().ok()
+        })
+        .collect()
+}
+
+impl Endpoint {
+    /// Parse `http://[user:password@]host[:port][/prefix]`.
+    fn new(url: &str, index: &str) -> Result<Self, FtailError> {
+        let invalid =
+            || FtailError::IoError(std::io::Error::other(format!("Invalid HTTP URL: {}", url)));
+
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+
+        let (credentials, host) = match authority.rsplit_once('@') {
+            Some((credentials, host)) => (Some(credentials), host),
+            None => (None, authority),
+        };
+
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        let host = match host.contains(':') {
+            true => host.to_string(),
+            false => format!("{}:9200", host),
+        };
+
+        let prefix = prefix.trim_end_matches('/');
+
+        Ok(Endpoint {
+            host,
+            path: match prefix.is_empty() {
+                true => format!("/{}/_bulk", index),
+                false => format!("/{}/{}/_bulk", prefix, index),
+            },
+            authorization: credentials.map(|credentials| base64(credentials.as_bytes())),
+        })
+    }
+
+    fn post(&self, body: &str) -> std::io::Result<(u16, String)> {
+        let mut stream = TcpStream::connect(&self.host)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.path,
+            self.host,
+            body.len()
+        );
+
+        if let Some(authorization) = &self.authorization {
+            request.push_str(&format!("Authorization: Basic {}\r\n", authorization));
+        }
+
+        request.push_str("\r\n");
+        request.push_str(body);
+
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        parse_response(&String::from_utf8_lossy(&response))
+    }
+}
+
+fn parse_response(response: &str) -> std::io::Result<(u16, String)> {
+    let invalid = || std::io::Error::other("Invalid HTTP response from Elasticsearch");
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or_else(invalid)?;
+
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(invalid)?;
+
+    let chunked = head
+        .lines()
+        .any(|line| line.eq_ignore_ascii_case("transfer-encoding: chunked"));
+
+    Ok((
+        status,
+        if chunked {
+            dechunk(body)
+        } else {
+            body.to_string()
+        },
+    ))
+}
+
+fn dechunk(mut body: &str) -> String {
+    let mut result = String::new();
+
+    while let Some((size, rest)) = body.split_once("\r\n") {
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+            .unwrap_or(0);
+
+        if size == 0 || rest.len() < size {
+            break;
+        }
+
+        result.push_str(&rest[..size]);
+        body = rest[size..].trim_start_matches("\r\n");
+    }
+
+    result
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, byte)| {
+            value | ((*byte as u32) << (16 - 8 * i))
+        });
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[((value >> (18 - 6 * i)) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+impl Log for ElasticsearchLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(Message::Document(self.document(record)));
+        }
+    }
+
+    /// Block until the queued records are sent.
+    fn flush(&self) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let (done, wait) = channel();
+
+        if sender.send(Message::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+impl Drop for ElasticsearchLogger {
+    fn drop(&mut self) {
+        // closing the queue stops the worker once it has sent the queued records
+        drop(self.sender.take());
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
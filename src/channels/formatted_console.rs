@@ -1,27 +1,43 @@
-use log::{LevelFilter, Log};
+use log::Log;
 
 use crate::{
-    formatters::{readable::ReadableFormatter, Formatter},
+    ansi_escape,
+    formatters::readable::ReadableFormatter,
+    helpers::{
+        flush_console_lines, format_message, prefix_terminal_title, print_console_line,
+        stderr_supports_escapes, stdout_supports_escapes,
+    },
     Config,
 };
 
-/// A logger that logs formatted messages to the console.
+/// A logger that logs formatted messages to the console. The colors are left out when `NO_COLOR`
+/// is set or the output is not a terminal, unless they are forced with `force_color`.
 pub struct FormattedConsoleLogger {
+    stdout_colors: bool,
+    stderr_colors: bool,
     config: Config,
 }
 
 impl FormattedConsoleLogger {
     pub fn new(config: Config) -> Self {
-        FormattedConsoleLogger { config }
+        FormattedConsoleLogger {
+            stdout_colors: stdout_supports_escapes(&config),
+            stderr_colors: stderr_supports_escapes(&config),
+            config,
+        }
+    }
+
+    /// Whether the stream the record is written to supports colors.
+    fn colors(&self, level: log::Level) -> bool {
+        match self.config.console_split_streams && level <= log::Level::Warn {
+            true => self.stderr_colors,
+            false => self.stdout_colors,
+        }
     }
 }
 
 impl Log for FormattedConsoleLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
         metadata.level() <= self.config.level_filter
     }
 
@@ -32,8 +48,20 @@ impl Log for FormattedConsoleLogger {
 
         let formatter = ReadableFormatter::new(record, &self.config);
 
-        println!("{}", formatter.format());
+        let mut line = format_message(record, &self.config, formatter);
+
+        if !self.colors(record.level()) {
+            line = ansi_escape::strip(&line);
+        }
+
+        prefix_terminal_title(&mut line, record, &self.config);
+
+        print_console_line(line, record.level(), &self.config);
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if self.config.console_batch_size.is_some() {
+            flush_console_lines();
+        }
+    }
 }
@@ -0,0 +1,133 @@
+use log::Log;
+use std::{
+    net::UdpSocket,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    error::FtailError,
+    formatters::json::escape,
+    helpers::{hostname, syslog_severity},
+    writer::LogWriter,
+    Config,
+};
+
+const CHUNK_SIZE: usize = 8192;
+const CHUNK_HEADER_SIZE: usize = 12;
+const MAX_CHUNKS: usize = 128;
+
+/// A logger that sends GELF (Graylog) messages over UDP.
+pub struct GelfLogger {
+    socket: UdpSocket,
+    host: String,
+    message_id: AtomicU64,
+    config: Config,
+}
+
+impl GelfLogger {
+    pub fn new(addr: &str, config: Config) -> Result<Self, FtailError> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(FtailError::IoError)?;
+
+        socket.connect(addr).map_err(FtailError::IoError)?;
+
+        let message_id = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64;
+
+        Ok(GelfLogger {
+            socket,
+            host: hostname(),
+            message_id: AtomicU64::new(message_id),
+            config,
+        })
+    }
+
+    fn format(&self, record: &log::Record) -> String {
+        let writer = LogWriter::new(record, &self.config);
+        let now = chrono::Utc::now();
+
+        let mut message = format!("{{\"version\":\"1.1\",\"host\":\"{}\"", escape(&self.host));
+
+        message.push_str(&format!(
+            ",\"short_message\":\"{}\"",
+            escape(&writer.get_args())
+        ));
+        message.push_str(&format!(
+            ",\"timestamp\":{}.{:03}",
+            now.timestamp(),
+            now.timestamp_subsec_millis()
+        ));
+        message.push_str(&format!(",\"level\":{}", syslog_severity(record.level())));
+        message.push_str(&format!(
+            ",\"_target\":\"{}\"",
+            escape(&writer.get_target())
+        ));
+
+        if let Some(version) = writer.get_version() {
+            message.push_str(&format!(",\"_version\":\"{}\"", escape(&version)));
+        }
+
+        if let Some(commit) = writer.get_commit() {
+            message.push_str(&format!(",\"_commit\":\"{}\"", escape(&commit)));
+        }
+
+        if let Some(file) = writer.get_file() {
+            message.push_str(&format!(",\"_file\":\"{}\"", escape(&file)));
+        }
+
+        if let Some(line) = writer.get_line() {
+            message.push_str(&format!(",\"_line\":{}", line));
+        }
+
+        message.push('}');
+
+        message
+    }
+
+    fn send(&self, message: &[u8]) {
+        if message.len() <= CHUNK_SIZE {
+            let _ = self.socket.send(message);
+
+            return;
+        }
+
+        let chunk_data_size = CHUNK_SIZE - CHUNK_HEADER_SIZE;
+        let chunks = message.chunks(chunk_data_size).collect::<Vec<&[u8]>>();
+
+        // messages that do not fit in the maximum number of chunks are discarded by Graylog
+        if chunks.len() > MAX_CHUNKS {
+            return;
+        }
+
+        let id = self
+            .message_id
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes();
+
+        for (sequence, chunk) in chunks.iter().enumerate() {
+            let mut datagram = Vec::with_capacity(CHUNK_HEADER_SIZE + chunk.len());
+
+            datagram.extend_from_slice(&[0x1e, 0x0f]);
+            datagram.extend_from_slice(&id);
+            datagram.push(sequence as u8);
+            datagram.push(chunks.len() as u8);
+            datagram.extend_from_slice(chunk);
+
+            let _ = self.socket.send(&datagram);
+        }
+    }
+}
+
+impl Log for GelfLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.send(self.format(record).as_bytes());
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,44 @@
+use log::Log;
+use std::sync::Arc;
+
+use crate::{
+    channels::daily_file::{DailyFileLogger, HOURLY_PATTERN},
+    error::FtailError,
+    helpers::CurrentFile,
+    Config,
+};
+
+/// A logger that logs messages to a new file every hour: `2024-09-13-14.log`. When the file
+/// exceeds `max_file_size`, the logger continues in the next file of the hour, and
+/// `retention_days` removes the files older than the number of days.
+pub struct HourlyFileLogger {
+    logger: DailyFileLogger,
+}
+
+impl HourlyFileLogger {
+    pub fn new(dir: &str, config: Config) -> Result<Self, FtailError> {
+        Ok(HourlyFileLogger {
+            logger: DailyFileLogger::with_pattern(dir, HOURLY_PATTERN, config)?,
+        })
+    }
+
+    pub(crate) fn with_current_file(self, current: Arc<CurrentFile>) -> Self {
+        HourlyFileLogger {
+            logger: self.logger.with_current_file(current),
+        }
+    }
+}
+
+impl Log for HourlyFileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.logger.log(record);
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
@@ -0,0 +1,77 @@
+use log::Log;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    formatters::default::DefaultFormatter,
+    helpers::{append_backtrace, format_message},
+    Config,
+};
+
+/// The last formatted lines of a logger, kept in memory, e.g. to show them in an admin panel.
+/// Cloning it is cheap, the clones share the same lines.
+#[derive(Clone)]
+pub struct MemoryBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl MemoryBuffer {
+    /// Create a buffer that keeps the last `capacity` lines.
+    pub fn new(capacity: usize) -> Self {
+        MemoryBuffer {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity.max(1)))),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// The kept lines, from the oldest to the most recent.
+    pub fn snapshot(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+
+        lines.push_back(line);
+    }
+}
+
+/// A logger that keeps the last formatted lines in a memory buffer.
+pub struct MemoryLogger {
+    buffer: MemoryBuffer,
+    config: Config,
+}
+
+impl MemoryLogger {
+    pub fn new(buffer: MemoryBuffer, config: Config) -> Self {
+        MemoryLogger { buffer, config }
+    }
+}
+
+impl Log for MemoryLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = DefaultFormatter::new(record, &self.config);
+
+        let mut message = format_message(record, &self.config, formatter);
+        append_backtrace(&mut message, record.level(), &self.config);
+
+        self.buffer.push(message);
+    }
+
+    fn flush(&self) {}
+}
@@ -1,6 +1,31 @@
+pub mod broadcast;
+pub mod callback;
 pub mod console;
+pub mod console_json;
+pub mod console_logfmt;
 pub mod daily_file;
+#[cfg(all(windows, feature = "windows"))]
+pub mod debug_output;
+pub mod dual_file;
+#[cfg(feature = "elasticsearch")]
+pub mod elasticsearch;
 pub mod formatted_console;
+#[cfg(feature = "gelf")]
+pub mod gelf;
+pub mod hourly_file;
+pub mod memory;
+pub mod mpsc;
+pub mod non_blocking;
+pub mod queued;
+pub mod shared_file;
 pub mod single_file;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod striped_files;
+#[cfg(feature = "syslog")]
+pub mod syslog;
+#[cfg(feature = "tcp")]
+pub mod tcp;
 #[cfg(test)]
 pub mod test;
+pub mod writer;
@@ -0,0 +1,56 @@
+use log::Log;
+use std::sync::mpsc::{Sender, SyncSender};
+
+use crate::{Config, RenderedRecord};
+
+/// The sending half of a channel that receives the records of the mpsc channel.
+#[derive(Clone)]
+pub enum RecordSender {
+    Unbounded(Sender<RenderedRecord>),
+    Bounded(SyncSender<RenderedRecord>),
+}
+
+impl From<Sender<RenderedRecord>> for RecordSender {
+    fn from(sender: Sender<RenderedRecord>) -> Self {
+        RecordSender::Unbounded(sender)
+    }
+}
+
+impl From<SyncSender<RenderedRecord>> for RecordSender {
+    fn from(sender: SyncSender<RenderedRecord>) -> Self {
+        RecordSender::Bounded(sender)
+    }
+}
+
+/// A logger that sends rendered records to an mpsc channel. Records are dropped when a bounded channel is full.
+pub struct MpscLogger {
+    sender: RecordSender,
+    config: Config,
+}
+
+impl MpscLogger {
+    pub fn new(sender: RecordSender, config: Config) -> Self {
+        MpscLogger { sender, config }
+    }
+}
+
+impl Log for MpscLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let record = RenderedRecord::new(record, &self.config);
+
+        let _ = match &self.sender {
+            RecordSender::Unbounded(sender) => sender.send(record).ok(),
+            RecordSender::Bounded(sender) => sender.try_send(record).ok(),
+        };
+    }
+
+    fn flush(&self) {}
+}
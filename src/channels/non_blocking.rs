@@ -0,0 +1,169 @@
+use log::Log;
+use std::{
+    sync::{
+        mpsc::{channel, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    channels::queued::OwnedRecord,
+    queue::{Dropped, Queue},
+    Config,
+};
+
+type Channel = Arc<QueuedChannel>;
+
+/// A file channel written by the background writer, with the number of its records dropped when
+/// the queue was full.
+struct QueuedChannel {
+    channel: Box<dyn Log + Send + Sync>,
+    dropped: Dropped,
+}
+
+/// A background thread shared by the file channels of a logger with `non_blocking`, which writes
+/// the records they queue so logging returns immediately. The queue is bounded by the
+/// `queue_capacity`, and the `overflow` policy applies when it is full.
+#[derive(Clone)]
+pub(crate) struct BackgroundWriter {
+    worker: Arc<Worker>,
+}
+
+struct Worker {
+    queue: Arc<Queue<Message>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+enum Message {
+    Record(Channel, OwnedRecord),
+    Flush(Channel, Sender<()>),
+}
+
+impl BackgroundWriter {
+    pub fn new(config: &Config) -> Self {
+        let queue = Arc::new(Queue::new(config.queue_capacity, config.overflow));
+
+        let thread = {
+            let queue = Arc::clone(&queue);
+
+            std::thread::Builder::new()
+                .name("ftail-writer".to_string())
+                .spawn(move || run(&queue))
+                .unwrap()
+        };
+
+        BackgroundWriter {
+            worker: Arc::new(Worker {
+                queue,
+                thread: Some(thread),
+            }),
+        }
+    }
+
+    fn send(&self, queued: &Channel, record: OwnedRecord) {
+        let dropped = self
+            .worker
+            .queue
+            .push(Message::Record(Arc::clone(queued), record));
+
+        // the dropped record may be the oldest one of another channel
+        if let Some(Message::Record(channel, _)) = dropped {
+            channel.dropped.add();
+        }
+    }
+
+    fn flush(&self, queued: &Channel) {
+        let (done, wait) = channel();
+
+        if self
+            .worker
+            .queue
+            .push_control(Message::Flush(Arc::clone(queued), done))
+        {
+            let _ = wait.recv();
+        }
+    }
+}
+
+fn run(queue: &Queue<Message>) {
+    while let Some(message) = queue.pop() {
+        match message {
+            Message::Record(channel, record) => {
+                record.log(channel.channel.as_ref());
+                channel.dropped.report(channel.channel.as_ref(), false);
+            }
+            Message::Flush(channel, done) => {
+                channel.dropped.report(channel.channel.as_ref(), true);
+                channel.channel.flush();
+
+                // release the channel before answering, so the logger that waits for the answer
+                // holds the last reference and drops the channel on its own thread
+                drop(channel);
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // closing the queue stops the thread once it has written the queued records
+        self.queue.close();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A file logger whose records are written by the background writer of the logger.
+pub struct NonBlockingLogger {
+    channel: Channel,
+    writer: BackgroundWriter,
+    config: Config,
+}
+
+impl NonBlockingLogger {
+    pub(crate) fn new(
+        channel: Box<dyn Log + Send + Sync>,
+        writer: BackgroundWriter,
+        config: Config,
+    ) -> Self {
+        NonBlockingLogger {
+            channel: Arc::new(QueuedChannel {
+                channel,
+                dropped: Dropped::new(),
+            }),
+            writer,
+            config,
+        }
+    }
+}
+
+impl Log for NonBlockingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.writer
+            .send(&self.channel, OwnedRecord::new(record, &self.config));
+    }
+
+    /// Block until the queued records of the channel are written and the channel is flushed.
+    fn flush(&self) {
+        self.writer.flush(&self.channel);
+    }
+}
+
+impl Drop for NonBlockingLogger {
+    fn drop(&mut self) {
+        // wait for the queued records, so the channel writes its footer after them
+        self.flush();
+    }
+}
@@ -0,0 +1,162 @@
+use log::Log;
+use std::{
+    sync::{
+        mpsc::{sync_channel, SyncSender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    kv::{self, KeyValue},
+    queue::{Dropped, Queue},
+    writer::{as_logging_thread, thread_name, LogWriter},
+    Config,
+};
+
+/// A logger that hands the records to another logger running on its own thread through a bounded
+/// queue, so a slow logger does not block the others. When the queue is full, logging blocks or
+/// a record is dropped, depending on the `overflow` policy.
+pub struct QueuedLogger {
+    queue: Arc<Queue<Message>>,
+    dropped: Arc<Dropped>,
+    worker: Option<JoinHandle<()>>,
+    config: Config,
+}
+
+enum Message {
+    Record(OwnedRecord),
+    Flush(SyncSender<()>),
+}
+
+/// A record that can be sent to another thread.
+pub(crate) struct OwnedRecord {
+    level: log::Level,
+    target: String,
+    args: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    #[cfg_attr(not(feature = "kv"), allow(dead_code))]
+    key_values: Vec<KeyValue>,
+    thread: String,
+}
+
+impl QueuedLogger {
+    pub fn new(channel: Box<dyn Log + Send + Sync>, capacity: usize, config: Config) -> Self {
+        let queue = Arc::new(Queue::new(Some(capacity), config.overflow));
+        let dropped = Arc::new(Dropped::new());
+
+        let worker = {
+            let queue = Arc::clone(&queue);
+            let dropped = Arc::clone(&dropped);
+
+            std::thread::Builder::new()
+                .name("ftail-queue".to_string())
+                .spawn(move || run(channel, &queue, &dropped))
+                .unwrap()
+        };
+
+        QueuedLogger {
+            queue,
+            dropped,
+            worker: Some(worker),
+            config,
+        }
+    }
+}
+
+fn run(channel: Box<dyn Log + Send + Sync>, queue: &Queue<Message>, dropped: &Dropped) {
+    while let Some(message) = queue.pop() {
+        match message {
+            Message::Record(record) => {
+                record.log(channel.as_ref());
+                dropped.report(channel.as_ref(), false);
+            }
+            Message::Flush(done) => {
+                dropped.report(channel.as_ref(), true);
+                channel.flush();
+                let _ = done.send(());
+            }
+        }
+    }
+
+    dropped.report(channel.as_ref(), true);
+
+    // the channel is dropped here, on the worker, once the queue is closed and drained
+}
+
+impl OwnedRecord {
+    /// Copy the record, with the message formatted as the channels do, so a failing `Display`
+    /// implementation does not panic and a message longer than `max_message_length` is cut.
+    pub fn new(record: &log::Record, config: &Config) -> Self {
+        OwnedRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            args: LogWriter::new(record, config).get_args(),
+            module_path: record.module_path().map(|path| path.to_string()),
+            file: record.file().map(|file| file.to_string()),
+            line: record.line(),
+            key_values: kv::collect(record),
+            thread: thread_name(),
+        }
+    }
+
+    pub fn log(&self, channel: &dyn Log) {
+        as_logging_thread(&self.thread, || {
+            let mut builder = log::Record::builder();
+
+            builder
+                .level(self.level)
+                .target(&self.target)
+                .module_path(self.module_path.as_deref())
+                .file(self.file.as_deref())
+                .line(self.line);
+
+            #[cfg(feature = "kv")]
+            builder.key_values(&self.key_values);
+
+            channel.log(&builder.args(format_args!("{}", self.args)).build());
+        });
+    }
+}
+
+impl Log for QueuedLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if self
+            .queue
+            .push(Message::Record(OwnedRecord::new(record, &self.config)))
+            .is_some()
+        {
+            self.dropped.add();
+        }
+    }
+
+    /// Block until the queued records are written and the channel is flushed.
+    fn flush(&self) {
+        let (done, wait) = sync_channel(1);
+
+        if self.queue.push_control(Message::Flush(done)) {
+            let _ = wait.recv();
+        }
+    }
+}
+
+impl Drop for QueuedLogger {
+    fn drop(&mut self) {
+        // closing the queue stops the worker once it has written the queued records
+        self.queue.close();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
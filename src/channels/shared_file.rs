@@ -0,0 +1,107 @@
+use log::Log;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{
+    error::FtailError,
+    helpers::{append_backtrace, lock, track_write, write_line},
+    Config, RecordFormat, WriteErrors,
+};
+
+/// A log file shared by several views, so the views write through a single file handle.
+#[derive(Clone)]
+pub struct SharedFile {
+    path: String,
+    append: bool,
+    writer: Arc<OnceLock<Arc<Mutex<BufWriter<File>>>>>,
+}
+
+impl SharedFile {
+    pub fn new(path: &str, append: bool) -> Self {
+        SharedFile {
+            path: path.to_string(),
+            append,
+            writer: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Open the file on first use, the following views reuse the same writer.
+    fn writer(&self) -> Result<Arc<Mutex<BufWriter<File>>>, FtailError> {
+        if let Some(writer) = self.writer.get() {
+            return Ok(writer.clone());
+        }
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(&self.path)
+            .map_err(FtailError::IoError)?;
+
+        let md = std::fs::metadata(&self.path).map_err(FtailError::IoError)?;
+
+        if md.permissions().readonly() {
+            return Err(FtailError::PermissionsError(self.path.clone()));
+        }
+
+        Ok(self
+            .writer
+            .get_or_init(|| Arc::new(Mutex::new(BufWriter::new(file))))
+            .clone())
+    }
+}
+
+/// A logger that logs messages to a shared log file with its own format.
+pub struct SharedFileLogger {
+    writer: Arc<Mutex<BufWriter<File>>>,
+    format: RecordFormat,
+    config: Config,
+    write_errors: WriteErrors,
+}
+
+impl SharedFileLogger {
+    pub fn new(
+        file: &SharedFile,
+        format: RecordFormat,
+        config: Config,
+    ) -> Result<Self, FtailError> {
+        Ok(SharedFileLogger {
+            writer: file.writer()?,
+            format,
+            config,
+            write_errors: WriteErrors::current(),
+        })
+    }
+}
+
+impl Log for SharedFileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut message = (self.format)(record, &self.config);
+        append_backtrace(&mut message, record.level(), &self.config);
+
+        // flush once per record rather than at every newline of a multi-line record, records larger
+        // than the buffer are written directly
+        let mut writer = lock(&self.writer);
+        track_write(
+            write_line(&mut *writer, &message, &self.config),
+            &self.write_errors,
+        );
+        track_write(writer.flush(), &self.write_errors);
+    }
+
+    fn flush(&self) {
+        track_write(lock(&self.writer).flush(), &self.write_errors);
+    }
+}
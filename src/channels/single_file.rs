@@ -1,23 +1,38 @@
-use log::{LevelFilter, Log};
+use log::Log;
 use std::{
     fs::File,
-    io::{LineWriter, Write},
+    io::{BufWriter, Write},
     path::PathBuf,
-    sync::Mutex,
+    sync::{atomic::Ordering, Arc, Mutex},
 };
 
 use crate::{
     error::FtailError,
-    formatters::{default::DefaultFormatter, Formatter},
-    helpers::rotate_if_exceeds_max_file_size,
-    Config,
+    formatters::{json::JsonFormatter, logfmt::LogfmtFormatter},
+    helpers::{
+        append_backtrace, finish_rotation, format_file_message, format_message, line_size, lock,
+        rotate_file, rotate_if_exceeds_max_file_size, should_flush, track_write, write_footer,
+        write_line, CurrentFile, LineNumbers, RecordCounter,
+    },
+    Config, WriteErrors,
 };
 
 /// A logger that logs messages to a single log file.
 pub struct SingleFileLogger {
-    file: Mutex<LineWriter<File>>,
-    file_path: PathBuf,
+    file: Mutex<BufWriter<File>>,
+    file_path: Option<PathBuf>,
+    current: Arc<CurrentFile>,
+    line_numbers: LineNumbers,
+    counter: RecordCounter,
+    format: LineFormat,
     config: Config,
+    write_errors: WriteErrors,
+}
+
+enum LineFormat {
+    Default,
+    Json,
+    Logfmt,
 }
 
 impl SingleFileLogger {
@@ -36,19 +51,80 @@ impl SingleFileLogger {
         }
 
         Ok(SingleFileLogger {
-            file: Mutex::new(LineWriter::new(file)),
-            file_path: PathBuf::from(path),
+            file: Mutex::new(BufWriter::new(file)),
+            file_path: Some(PathBuf::from(path)),
+            current: CurrentFile::new(Some(PathBuf::from(path)), md.len()),
+            line_numbers: LineNumbers::new(&config),
+            counter: RecordCounter::new(),
+            format: LineFormat::Default,
+            config,
+            write_errors: WriteErrors::current(),
+        })
+    }
+
+    /// Log newline-delimited JSON, one object per message.
+    pub fn json(path: &str, append: bool, config: Config) -> Result<Self, FtailError> {
+        let mut logger = Self::new(path, append, config)?;
+        logger.format = LineFormat::Json;
+
+        Ok(logger)
+    }
+
+    /// Log logfmt, e.g. `ts=... level=info target=app msg="Hello, world!"`.
+    pub fn logfmt(path: &str, append: bool, config: Config) -> Result<Self, FtailError> {
+        let mut logger = Self::new(path, append, config)?;
+        logger.format = LineFormat::Logfmt;
+
+        Ok(logger)
+    }
+
+    /// Log to a file that is already open. The file is not rotated, as its path is unknown.
+    pub fn from_file(file: File, config: Config) -> Result<Self, FtailError> {
+        let md = file.metadata().map_err(FtailError::IoError)?;
+
+        Ok(SingleFileLogger {
+            file: Mutex::new(BufWriter::new(file)),
+            file_path: None,
+            current: CurrentFile::new(None, md.len()),
+            line_numbers: LineNumbers::new(&config),
+            counter: RecordCounter::new(),
+            format: LineFormat::Default,
             config,
+            write_errors: WriteErrors::current(),
         })
     }
+
+    /// Share the path and size of the file through `current`, e.g. to query them from the handle.
+    pub(crate) fn with_current_file(mut self, current: Arc<CurrentFile>) -> Self {
+        current.replace(&self.current);
+        self.current = current;
+
+        self
+    }
+
+    /// Rotate the file when the handle requested it with `rotate_now`.
+    fn rotate_if_requested(&self) {
+        let Some(file_path) = &self.file_path else {
+            return;
+        };
+
+        if !self.current.rotate.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        let mut file = lock(&self.file);
+        let rotated = rotate_file(&mut file, file_path, &self.current.size, &self.line_numbers);
+        drop(file);
+
+        match rotated {
+            Ok(rotated) => finish_rotation(file_path, &rotated, &self.config, &self.write_errors),
+            Err(error) => self.write_errors.push(error),
+        }
+    }
 }
 
 impl Log for SingleFileLogger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.level_filter == LevelFilter::Off {
-            return true;
-        }
-
         metadata.level() <= self.config.level_filter
     }
 
@@ -57,16 +133,67 @@ impl Log for SingleFileLogger {
             return;
         }
 
-        rotate_if_exceeds_max_file_size(&self.file, self.file_path.clone(), &self.config);
+        self.rotate_if_requested();
+
+        if let Some(file_path) = &self.file_path {
+            rotate_if_exceeds_max_file_size(
+                &self.file,
+                file_path.clone(),
+                &self.current.size,
+                &self.line_numbers,
+                &self.config,
+                &self.write_errors,
+            );
+        }
+
+        let (mut file, message, line_no) =
+            self.line_numbers.lock_and_format(&self.file, |line_no| {
+                let mut message = match self.format {
+                    LineFormat::Default => format_file_message(record, &self.config, line_no),
+                    LineFormat::Json => format_message(
+                        record,
+                        &self.config,
+                        JsonFormatter::new(record, &self.config),
+                    ),
+                    LineFormat::Logfmt => format_message(
+                        record,
+                        &self.config,
+                        LogfmtFormatter::new(record, &self.config),
+                    ),
+                };
+                append_backtrace(&mut message, record.level(), &self.config);
+
+                message
+            });
 
-        let formatter = DefaultFormatter::new(record, &self.config);
+        if track_write(
+            write_line(&mut *file, &message, &self.config),
+            &self.write_errors,
+        ) {
+            self.line_numbers.written(line_no);
+        }
+        self.current
+            .size
+            .fetch_add(line_size(&message, &self.config), Ordering::AcqRel);
 
-        let mut file = self.file.lock().unwrap();
-        writeln!(file, "{}", formatter.format()).unwrap();
-        file.flush().unwrap();
+        let records = self.counter.count(record.level());
+
+        if should_flush(records, record.level(), &self.config) {
+            track_write(file.flush(), &self.write_errors);
+        }
     }
 
     fn flush(&self) {
-        self.file.lock().unwrap().flush().unwrap();
+        self.rotate_if_requested();
+
+        track_write(lock(&self.file).flush(), &self.write_errors);
+    }
+}
+
+impl Drop for SingleFileLogger {
+    fn drop(&mut self) {
+        if let Ok(file) = self.file.get_mut() {
+            write_footer(file, &self.counter, &self.config, &self.write_errors);
+        }
     }
 }
@@ -0,0 +1,229 @@
+use log::Log;
+use rusqlite::{params, Connection};
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{error::FtailError, helpers::track_write, kv, writer::LogWriter, Config, WriteErrors};
+
+const MAX_BATCH_SIZE: usize = 512;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS logs (
+    id INTEGER PRIMARY KEY,
+    ts TEXT NOT NULL,
+    level TEXT NOT NULL,
+    target TEXT NOT NULL,
+    file TEXT,
+    line INTEGER,
+    message TEXT NOT NULL,
+    kvs TEXT
+);
+CREATE INDEX IF NOT EXISTS logs_ts ON logs (ts);";
+
+/// A logger that inserts the records into a SQLite database. The rows are written by a background
+/// thread, in batches of one transaction each.
+pub struct SqliteLogger {
+    sender: Option<Sender<Message>>,
+    worker: Option<JoinHandle<()>>,
+    config: Config,
+}
+
+enum Message {
+    Row(Row),
+    Flush(Sender<()>),
+}
+
+struct Row {
+    ts: String,
+    level: String,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+    kvs: Option<String>,
+}
+
+impl SqliteLogger {
+    pub fn new(path: &str, config: Config) -> Result<Self, FtailError> {
+        let connection = Connection::open(path).map_err(sqlite_error)?;
+
+        connection.execute_batch(SCHEMA).map_err(sqlite_error)?;
+
+        let (sender, receiver) = channel();
+        let worker_config = config.clone();
+        let write_errors = WriteErrors::current();
+
+        let worker = std::thread::Builder::new()
+            .name("ftail-sqlite".to_string())
+            .spawn(move || run(connection, receiver, worker_config, write_errors))
+            .map_err(FtailError::IoError)?;
+
+        Ok(SqliteLogger {
+            sender: Some(sender),
+            worker: Some(worker),
+            config,
+        })
+    }
+}
+
+fn run(
+    mut connection: Connection,
+    receiver: Receiver<Message>,
+    config: Config,
+    write_errors: WriteErrors,
+) {
+    let mut last_cleanup = None;
+
+    while let Ok(message) = receiver.recv() {
+        let mut rows = Vec::new();
+        let mut flushes = Vec::new();
+
+        let mut next = Some(message);
+
+        // drain whatever else is already queued so it goes into the same transaction
+        while let Some(message) = next {
+            match message {
+                Message::Row(row) => rows.push(row),
+                Message::Flush(done) => flushes.push(done),
+            }
+
+            next = if rows.len() < MAX_BATCH_SIZE {
+                receiver.try_recv().ok()
+            } else {
+                None
+            };
+        }
+
+        if !rows.is_empty() {
+            track_write(insert(&mut connection, &rows), &write_errors);
+        }
+
+        if let Some(retention_days) = config.retention_days {
+            if last_cleanup.map_or(true, |at: Instant| at.elapsed() >= RETENTION_INTERVAL) {
+                track_write(
+                    remove_old_rows(&connection, retention_days, &config),
+                    &write_errors,
+                );
+                last_cleanup = Some(Instant::now());
+            }
+        }
+
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn insert(connection: &mut Connection, rows: &[Row]) -> std::io::Result<()> {
+    let transaction = connection.transaction().map_err(std::io::Error::other)?;
+
+    {
+        let mut statement = transaction
+            .prepare_cached(
+                "INSERT INTO logs (ts, level, target, file, line, message, kvs)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .map_err(std::io::Error::other)?;
+
+        for row in rows {
+            statement
+                .execute(params![
+                    row.ts,
+                    row.level,
+                    row.target,
+                    row.file,
+                    row.line,
+                    row.message,
+                    row.kvs
+                ])
+                .map_err(std::io::Error::other)?;
+        }
+    }
+
+    transaction.commit().map_err(std::io::Error::other)
+}
+
+/// Remove the rows older than `retention_days`. The timestamps are stored as RFC 3339 in UTC, so
+/// they compare correctly as text.
+fn remove_old_rows(
+    connection: &Connection,
+    retention_days: u64,
+    config: &Config,
+) -> std::io::Result<usize> {
+    let cutoff = (config.clock)().with_timezone(&chrono::Utc)
+        - chrono::Duration::days(retention_days as i64);
+
+    connection
+        .execute(
+            "DELETE FROM logs WHERE ts < ?1",
+            params![cutoff.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)],
+        )
+        .map_err(std::io::Error::other)
+}
+
+fn sqlite_error(error: rusqlite::Error) -> FtailError {
+    FtailError::IoError(std::io::Error::other(error))
+}
+
+impl Row {
+    fn new(record: &log::Record, config: &Config) -> Self {
+        let writer = LogWriter::new(record, config);
+
+        Row {
+            ts: (config.clock)()
+                .with_timezone(&chrono::Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            level: record.level().to_string(),
+            target: writer.get_target(),
+            file: writer.get_file(),
+            line: writer.get_line(),
+            message: writer.get_args(),
+            kvs: Some(writer.get_key_values())
+                .filter(|key_values| !key_values.is_empty())
+                .map(|key_values| kv::format_json(&key_values)),
+        }
+    }
+}
+
+impl Log for SqliteLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(Message::Row(Row::new(record, &self.config)));
+        }
+    }
+
+    /// Block until the queued rows are committed.
+    fn flush(&self) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let (done, wait) = channel();
+
+        if sender.send(Message::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+impl Drop for SqliteLogger {
+    fn drop(&mut self) {
+        // closing the queue stops the worker once it has committed the queued rows
+        drop(self.sender.take());
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use log::Log;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{
+    error::FtailError,
+    formatters::default::DefaultFormatter,
+    helpers::{
+        append_backtrace, format_message, line_size, lock, should_flush, track_write, write_footer,
+        write_line, RecordCounter,
+    },
+    Config, WriteErrors,
+};
+
+/// A logger that logs messages across multiple files, advancing to the next file when the current one is full.
+pub struct StripedFilesLogger {
+    stripes: Vec<Stripe>,
+    current: AtomicUsize,
+    max_size: u64,
+    counter: RecordCounter,
+    config: Config,
+    write_errors: WriteErrors,
+}
+
+struct Stripe {
+    path: PathBuf,
+    file: Mutex<StripeWriter>,
+}
+
+struct StripeWriter {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl StripedFilesLogger {
+    pub fn new(dir: &str, n: usize, max_size: u64, config: Config) -> Result<Self, FtailError> {
+        let md = std::fs::metadata(dir).map_err(FtailError::IoError)?;
+
+        if md.permissions().readonly() {
+            return Err(FtailError::PermissionsError(dir.to_string()));
+        }
+
+        let stripes = (0..n.max(1))
+            .map(|i| {
+                let path = PathBuf::from(format!("{}/part{}.log", dir, i));
+
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .map_err(FtailError::IoError)?;
+
+                let size = file.metadata().map_err(FtailError::IoError)?.len();
+
+                Ok(Stripe {
+                    path,
+                    file: Mutex::new(StripeWriter {
+                        writer: BufWriter::new(file),
+                        size,
+                    }),
+                })
+            })
+            .collect::<Result<Vec<Stripe>, FtailError>>()?;
+
+        Ok(StripedFilesLogger {
+            stripes,
+            current: AtomicUsize::new(0),
+            max_size,
+            counter: RecordCounter::new(),
+            config,
+            write_errors: WriteErrors::current(),
+        })
+    }
+
+    fn write(&self, line: &str, flush: bool) {
+        loop {
+            let index = self.current.load(Ordering::Acquire);
+            let mut stripe = lock(&self.stripes[index].file);
+
+            // another thread advanced to the next stripe while we were waiting for the lock
+            if self.current.load(Ordering::Acquire) != index {
+                continue;
+            }
+
+            if stripe.size < self.max_size {
+                track_write(
+                    write_line(&mut stripe.writer, line, &self.config),
+                    &self.write_errors,
+                );
+
+                if flush {
+                    track_write(stripe.writer.flush(), &self.write_errors);
+                }
+                stripe.size += line_size(line, &self.config);
+
+                return;
+            }
+
+            let next = (index + 1) % self.stripes.len();
+
+            if next == index {
+                stripe.truncate(&self.stripes[index].path);
+            } else {
+                self.stripes[next]
+                    .file
+                    .lock()
+                    .unwrap()
+                    .truncate(&self.stripes[next].path);
+            }
+
+            self.current.store(next, Ordering::Release);
+        }
+    }
+}
+
+impl StripeWriter {
+    fn truncate(&mut self, path: &Path) {
+        let file = std::fs::File::create(path).unwrap();
+
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+    }
+}
+
+impl Log for StripedFilesLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = DefaultFormatter::new(record, &self.config);
+
+        let mut message = format_message(record, &self.config, formatter);
+        append_backtrace(&mut message, record.level(), &self.config);
+
+        let records = self.counter.count(record.level());
+
+        self.write(
+            &message,
+            should_flush(records, record.level(), &self.config),
+        );
+    }
+
+    fn flush(&self) {
+        for stripe in &self.stripes {
+            track_write(lock(&stripe.file).writer.flush(), &self.write_errors);
+        }
+    }
+}
+
+impl Drop for StripedFilesLogger {
+    fn drop(&mut self) {
+        let index = *self.current.get_mut();
+
+        if let Ok(stripe) = self.stripes[index].file.get_mut() {
+            write_footer(
+                &mut stripe.writer,
+                &self.counter,
+                &self.config,
+                &self.write_errors,
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use log::Log;
+use std::{net::UdpSocket, sync::Mutex};
+
+use crate::{
+    error::FtailError,
+    helpers::{hostname, syslog_severity, track_write},
+    retry::Connection,
+    writer::LogWriter,
+    Config, WriteErrors,
+};
+
+/// A logger that sends RFC 5424 syslog messages, e.g. to rsyslog. Over UDP each message is a
+/// datagram, over TCP the messages are framed with octet counting and the connection is opened
+/// again after an error.
+pub struct SyslogLogger {
+    transport: Transport,
+    facility: u8,
+    host: String,
+    app_name: String,
+    config: Config,
+    write_errors: WriteErrors,
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(Mutex<Connection>),
+}
+
+impl SyslogLogger {
+    /// Send to `udp://host:port` or `tcp://host:port`, UDP when there is no scheme.
+    pub fn new(addr: &str, facility: u8, config: Config) -> Result<Self, FtailError> {
+        let transport = match addr.split_once("://") {
+            Some(("tcp", addr)) => Transport::Tcp(Mutex::new(Connection::new(addr))),
+            Some(("udp", addr)) => Transport::Udp(udp_socket(addr)?),
+            None => Transport::Udp(udp_socket(addr)?),
+            Some(_) => {
+                return Err(FtailError::IoError(std::io::Error::other(format!(
+                    "Invalid syslog address: {}",
+                    addr
+                ))))
+            }
+        };
+
+        Ok(SyslogLogger {
+            transport,
+            facility: facility.min(23),
+            host: hostname(),
+            app_name: app_name(),
+            config,
+            write_errors: WriteErrors::current(),
+        })
+    }
+
+    fn format(&self, record: &log::Record) -> String {
+        let writer = LogWriter::new(record, &self.config);
+        let timestamp = (self.config.clock)()
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+        format!(
+            "<{}>1 {} {} {} {} {} - {}",
+            self.facility as u16 * 8 + syslog_severity(record.level()) as u16,
+            timestamp,
+            header_field(&self.host, 255),
+            header_field(&self.app_name, 48),
+            std::process::id(),
+            header_field(&writer.get_target(), 32),
+            writer.get_args()
+        )
+    }
+}
+
+fn udp_socket(addr: &str) -> Result<UdpSocket, FtailError> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(FtailError::IoError)?;
+
+    socket.connect(addr).map_err(FtailError::IoError)?;
+
+    Ok(socket)
+}
+
+/// The name of the executable, for the APP-NAME of the messages.
+fn app_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+/// A header field of the message, limited to printable ASCII and the maximum length of the
+/// field, or `-` when it is empty.
+fn header_field(value: &str, max_len: usize) -> String {
+    let field = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect::<String>();
+
+    match field.is_empty() {
+        true => "-".to_string(),
+        false => field,
+    }
+}
+
+impl Log for SyslogLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = self.format(record);
+
+        match &self.transport {
+            Transport::Udp(socket) => {
+                track_write(socket.send(message.as_bytes()), &self.write_errors);
+            }
+            Transport::Tcp(connection) => {
+                let frame = format!("{} {}", message.len(), message);
+
+                track_write(
+                    connection.lock().unwrap().write(
+                        frame.as_bytes(),
+                        true,
+                        &self.config.retry_policy,
+                    ),
+                    &self.write_errors,
+                );
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
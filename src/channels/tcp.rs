@@ -0,0 +1,63 @@
+use log::Log;
+use std::sync::Mutex;
+
+use crate::{
+    formatters::default::DefaultFormatter,
+    helpers::{append_backtrace, format_message, should_flush, track_write, RecordCounter},
+    retry::Connection,
+    Config, WriteErrors,
+};
+
+/// A logger that streams formatted messages to a TCP server, one line per message. The
+/// connection is opened again on the next message after an error, instead of failing the logger.
+pub struct TcpLogger {
+    connection: Mutex<Connection>,
+    counter: RecordCounter,
+    config: Config,
+    write_errors: WriteErrors,
+}
+
+impl TcpLogger {
+    pub fn new(addr: &str, config: Config) -> Self {
+        TcpLogger {
+            connection: Mutex::new(Connection::new(addr)),
+            counter: RecordCounter::new(),
+            config,
+            write_errors: WriteErrors::current(),
+        }
+    }
+}
+
+impl Log for TcpLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let formatter = DefaultFormatter::new(record, &self.config);
+
+        let mut message = format_message(record, &self.config, formatter);
+        append_backtrace(&mut message, record.level(), &self.config);
+        message.push('\n');
+
+        let records = self.counter.count(record.level());
+        let flush = should_flush(records, record.level(), &self.config);
+
+        track_write(
+            self.connection.lock().unwrap().write(
+                message.as_bytes(),
+                flush,
+                &self.config.retry_policy,
+            ),
+            &self.write_errors,
+        );
+    }
+
+    fn flush(&self) {
+        track_write(self.connection.lock().unwrap().flush(), &self.write_errors);
+    }
+}
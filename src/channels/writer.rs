@@ -0,0 +1,79 @@
+use log::Log;
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    formatters::default::DefaultFormatter,
+    helpers::{
+        append_backtrace, format_message, lock, should_flush, track_write, write_footer,
+        write_line, RecordCounter,
+    },
+    Config, WriteErrors,
+};
+
+/// A logger that logs formatted messages to any writer, e.g. a `Vec<u8>`, a pipe or a socket.
+pub struct WriterLogger<W: Write + Send> {
+    writer: Arc<Mutex<W>>,
+    counter: RecordCounter,
+    config: Config,
+    write_errors: WriteErrors,
+}
+
+impl<W: Write + Send> WriterLogger<W> {
+    pub fn new(writer: Arc<Mutex<W>>, config: Config) -> Self {
+        WriterLogger {
+            writer,
+            counter: RecordCounter::new(),
+            config,
+            write_errors: WriteErrors::current(),
+        }
+    }
+}
+
+impl<W: Write + Send> Log for WriterLogger<W> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.config.level_filter
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut message = format_message(
+            record,
+            &self.config,
+            DefaultFormatter::new(record, &self.config),
+        );
+        append_backtrace(&mut message, record.level(), &self.config);
+
+        let mut writer = lock(&self.writer);
+        track_write(
+            write_line(&mut *writer, &message, &self.config),
+            &self.write_errors,
+        );
+
+        let records = self.counter.count(record.level());
+
+        if should_flush(records, record.level(), &self.config) {
+            track_write(writer.flush(), &self.write_errors);
+        }
+    }
+
+    fn flush(&self) {
+        track_write(lock(&self.writer).flush(), &self.write_errors);
+    }
+}
+
+impl<W: Write + Send> Drop for WriterLogger<W> {
+    fn drop(&mut self) {
+        write_footer(
+            &mut *lock(&self.writer),
+            &self.counter,
+            &self.config,
+            &self.write_errors,
+        );
+    }
+}
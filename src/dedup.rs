@@ -0,0 +1,83 @@
+//! The collapsing of repeated records with `dedup_repeats`.
+
+use log::Level;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{helpers::lock, writer::LogWriter, Config};
+
+/// The last record logged, with the number of times it was repeated since.
+#[derive(Default)]
+pub(crate) struct Repeats(Mutex<Option<Run>>);
+
+struct Run {
+    level: Level,
+    target: String,
+    message: String,
+    started_at: Instant,
+    repeated: u64,
+}
+
+/// A run of repeated records that ended, to be reported with a single record.
+pub(crate) struct Repeated {
+    pub level: Level,
+    pub target: String,
+    pub times: u64,
+}
+
+impl Repeats {
+    /// Count the record when it repeats the last record within the window since that record was
+    /// logged, returning `None` so it is not logged. Otherwise the record starts a new run and the
+    /// previous run is returned, when its record was repeated. The messages are compared as the
+    /// channels format them, cut at `max_message_length`.
+    pub fn check(
+        &self,
+        record: &log::Record,
+        window: Duration,
+        config: &Config,
+    ) -> Option<Option<Repeated>> {
+        let message = LogWriter::new(record, config).get_args();
+        let mut run = lock(&self.0);
+
+        if let Some(run) = run.as_mut().filter(|run| {
+            run.level == record.level()
+                && run.target == record.target()
+                && run.message == message
+                && run.started_at.elapsed() < window
+        }) {
+            run.repeated += 1;
+
+            return None;
+        }
+
+        let ended = run.take().and_then(Run::repeated);
+
+        *run = Some(Run {
+            level: record.level(),
+            target: record.target().to_string(),
+            message,
+            started_at: Instant::now(),
+            repeated: 0,
+        });
+
+        Some(ended)
+    }
+
+    /// Take the repeats of the current run, e.g. to report them when the logger is flushed. The
+    /// next identical record starts a new run.
+    pub fn take(&self) -> Option<Repeated> {
+        lock(&self.0).take().and_then(Run::repeated)
+    }
+}
+
+impl Run {
+    fn repeated(self) -> Option<Repeated> {
+        (self.repeated > 0).then_some(Repeated {
+            level: self.level,
+            target: self.target,
+            times: self.repeated,
+        })
+    }
+}
@@ -8,6 +8,11 @@ pub enum FtailError {
     NoChannelsError,
     IoError(std::io::Error),
     PermissionsError(String),
+    InvalidDatetimeFormat(String),
+    InvalidSize(String),
+    InvalidRegex(String),
+    EnvParseError(String, String),
+    WriteErrors(u64, std::io::Error),
 }
 
 impl std::error::Error for FtailError {}
@@ -21,6 +26,17 @@ impl Display for FtailError {
             FtailError::PermissionsError(path) => {
                 write!(f, "The path {} is read-only", path)
             }
+            FtailError::InvalidDatetimeFormat(format) => {
+                write!(f, "Invalid datetime format: {}", format)
+            }
+            FtailError::InvalidSize(size) => write!(f, "Invalid size: {}", size),
+            FtailError::InvalidRegex(error) => write!(f, "Invalid regex: {}", error),
+            FtailError::EnvParseError(var, value) => {
+                write!(f, "Invalid value for {}: {}", var, value)
+            }
+            FtailError::WriteErrors(count, error) => {
+                write!(f, "{} writes failed, the first error was: {}", count, error)
+            }
         }
     }
 }
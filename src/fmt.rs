@@ -0,0 +1,54 @@
+//! Helpers to format values consistently in log messages.
+
+use std::{fmt::Display, time::Duration};
+
+/// The unit a duration is formatted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unit {
+    Nanos,
+    Micros,
+    #[default]
+    Millis,
+    Secs,
+}
+
+/// A duration formatted in a fixed unit, created with `dur`.
+#[derive(Debug, Clone, Copy)]
+pub struct Dur {
+    duration: Duration,
+    unit: Unit,
+}
+
+/// Format a duration in milliseconds, e.g. `1234.568ms`, so latencies are logged the same way
+/// across an application. The precision defaults to 3 decimals and can be set in the format
+/// string, e.g. `{:.1}`. Use `unit` to format in another unit.
+pub fn dur(duration: Duration) -> Dur {
+    Dur {
+        duration,
+        unit: Unit::default(),
+    }
+}
+
+impl Dur {
+    /// Format the duration in the given unit.
+    pub fn unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+
+        self
+    }
+}
+
+impl Display for Dur {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nanos = self.duration.as_nanos() as f64;
+
+        let (value, suffix) = match self.unit {
+            Unit::Nanos => return write!(f, "{}ns", self.duration.as_nanos()),
+            Unit::Micros => (nanos / 1e3, "µs"),
+            Unit::Millis => (nanos / 1e6, "ms"),
+            Unit::Secs => (nanos / 1e9, "s"),
+        };
+
+        write!(f, "{:.*}{}", f.precision().unwrap_or(3), value, suffix)
+    }
+}
@@ -1,15 +1,65 @@
-use crate::{formatters::Config, writer::LogWriter};
+use crate::{formatters::Config, kv, writer::LogWriter};
 
-use super::Formatter;
+use super::{template::TemplateFormatter, Formatter};
+
+/// Records logged with this target are written verbatim, without the datetime, level and target.
+/// It is namespaced so that it cannot be the module path of a `log` macro outside of ftail.
+pub const RAW_TARGET: &str = "ftail::raw";
+
+/// A field of the default format whose overflow can be set with `field_overflow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Target,
+    Message,
+}
+
+/// What to do with a field longer than the given number of characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Cut the field, ending it with `…`.
+    Truncate(usize),
+    /// Continue the field on the next lines, indented to its column. Only the console wraps, so
+    /// the files keep one record per line.
+    Wrap(usize),
+}
 
 pub struct DefaultFormatter<'a> {
     record: &'a log::Record<'a>,
     config: &'a Config,
+    line_no: Option<u64>,
+    wrap: bool,
 }
 
 impl DefaultFormatter<'_> {
     pub fn new<'a>(record: &'a log::Record<'a>, config: &'a Config) -> DefaultFormatter<'a> {
-        DefaultFormatter { record, config }
+        DefaultFormatter {
+            record,
+            config,
+            line_no: None,
+            wrap: false,
+        }
+    }
+
+    /// Wrap the fields set to `Overflow::Wrap`, for the console.
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+
+        self
+    }
+
+    fn fit(&self, value: String, overflow: Option<Overflow>, line: &str) -> String {
+        match overflow {
+            Some(Overflow::Truncate(width)) => truncate(value, width),
+            Some(Overflow::Wrap(width)) if self.wrap => wrap(&value, width, column(line)),
+            _ => value,
+        }
+    }
+
+    /// Set the number of the line in the file, for the `{line_no}` token of the template.
+    pub fn with_line_no(mut self, line_no: Option<u64>) -> Self {
+        self.line_no = line_no;
+
+        self
     }
 }
 
@@ -17,12 +67,93 @@ impl Formatter for DefaultFormatter<'_> {
     fn format(&self) -> String {
         let writer = LogWriter::new(self.record, self.config);
 
-        format!(
-            "{} {} {} {}",
-            writer.get_datetime(),
-            writer.get_level(),
-            writer.get_target(),
-            writer.get_args(),
-        )
+        if self.record.target() == RAW_TARGET {
+            return writer.get_args();
+        }
+
+        if self.config.template.is_some() {
+            return TemplateFormatter::new(self.record, self.config)
+                .with_line_no(self.line_no)
+                .format();
+        }
+
+        let mut result = writer.get_datetime();
+
+        if let Some(version) = writer.get_version() {
+            result.push_str(&format!(" {}", version));
+
+            if let Some(commit) = writer.get_commit() {
+                result.push_str(&format!("@{}", commit));
+            }
+        }
+
+        if let Some(pid) = writer.get_pid() {
+            result.push_str(&format!(" [{}]", pid));
+        }
+
+        result.push_str(&format!(" {} ", writer.get_level()));
+
+        if let Some(thread) = writer.get_thread() {
+            result.push_str(&format!("[{}] ", thread));
+        }
+
+        let target = self.fit(writer.get_target(), self.config.target_overflow, &result);
+        result.push_str(&format!("{} ", target));
+
+        let message = self.fit(writer.get_args(), self.config.message_overflow, &result);
+        result.push_str(&message);
+
+        let key_values = writer.get_key_values();
+
+        if !key_values.is_empty() {
+            // a record with only key-values has no message to separate them from
+            if !message.is_empty() {
+                result.push(' ');
+            }
+
+            result.push_str(&kv::format_text(&key_values));
+        }
+
+        result
+    }
+}
+
+fn truncate(value: String, width: usize) -> String {
+    if value.chars().count() <= width {
+        return value;
     }
+
+    let mut truncated = value
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+
+    truncated
+}
+
+/// Split the lines of the value into lines of `width` characters, indented to `indent`.
+fn wrap(value: &str, width: usize, indent: usize) -> String {
+    let lines = value
+        .split('\n')
+        .flat_map(|line| {
+            let chars = line.chars().collect::<Vec<char>>();
+
+            if chars.is_empty() {
+                return vec![String::new()];
+            }
+
+            chars
+                .chunks(width.max(1))
+                .map(|chunk| chunk.iter().collect::<String>())
+                .collect()
+        })
+        .collect::<Vec<String>>();
+
+    lines.join(&format!("\n{}", " ".repeat(indent)))
+}
+
+/// The column at the end of the last line of the text.
+fn column(text: &str) -> usize {
+    text.rsplit('\n').next().unwrap_or_default().chars().count()
 }
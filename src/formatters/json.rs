@@ -0,0 +1,120 @@
+use crate::{kv, writer::LogWriter, Config};
+
+use super::Formatter;
+
+/// The version of the JSON format of ftail, for the `_schema` field set with `json_schema`.
+pub const JSON_SCHEMA: &str = "ftail/1";
+
+/// The fields written by the formatter, which the key-values are not promoted over.
+const FIELDS: &[&str] = &[
+    "_schema",
+    "timestamp",
+    "level",
+    "target",
+    "message",
+    "pid",
+    "thread",
+    "fields",
+    "file",
+    "line",
+    "version",
+    "commit",
+];
+
+pub struct JsonFormatter<'a> {
+    record: &'a log::Record<'a>,
+    config: &'a Config,
+}
+
+impl JsonFormatter<'_> {
+    pub fn new<'a>(record: &'a log::Record<'a>, config: &'a Config) -> JsonFormatter<'a> {
+        JsonFormatter { record, config }
+    }
+}
+
+impl Formatter for JsonFormatter<'_> {
+    fn format(&self) -> String {
+        let writer = LogWriter::new(self.record, self.config);
+
+        let mut result = String::from("{");
+
+        if let Some(schema) = &self.config.json_schema {
+            result.push_str(&format!("\"_schema\":\"{}\",", escape(schema)));
+        }
+
+        result.push_str(&format!(
+            "\"timestamp\":\"{}\"",
+            escape(&writer.get_datetime())
+        ));
+
+        result.push_str(&format!(",\"level\":\"{}\"", writer.get_level()));
+        result.push_str(&format!(",\"target\":\"{}\"", escape(&writer.get_target())));
+        result.push_str(&format!(",\"message\":\"{}\"", escape(&writer.get_args())));
+
+        if let Some(pid) = writer.get_pid() {
+            result.push_str(&format!(",\"pid\":{}", pid));
+        }
+
+        if let Some(thread) = writer.get_thread() {
+            result.push_str(&format!(",\"thread\":\"{}\"", escape(&thread)));
+        }
+
+        // a key named after a field of the format stays in `fields`, so no key is written twice
+        let keys = self
+            .config
+            .json_promoted_keys
+            .as_deref()
+            .unwrap_or_default();
+        let (promoted, key_values) = kv::promote(
+            writer.get_key_values(),
+            keys.iter().filter(|key| !FIELDS.contains(&key.as_str())),
+        );
+
+        if !promoted.is_empty() {
+            result.push_str(&format!(",{}", kv::format_json_members(&promoted)));
+        }
+
+        if !key_values.is_empty() {
+            result.push_str(&format!(",\"fields\":{}", kv::format_json(&key_values)));
+        }
+
+        if let Some(file) = writer.get_file() {
+            result.push_str(&format!(",\"file\":\"{}\"", escape(&file)));
+        }
+
+        if let Some(line) = writer.get_line() {
+            result.push_str(&format!(",\"line\":{}", line));
+        }
+
+        if let Some(version) = writer.get_version() {
+            result.push_str(&format!(",\"version\":\"{}\"", escape(&version)));
+        }
+
+        if let Some(commit) = writer.get_commit() {
+            result.push_str(&format!(",\"commit\":\"{}\"", escape(&commit)));
+        }
+
+        result.push('}');
+
+        result
+    }
+}
+
+/// Escape a value to be used in a JSON string.
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,70 @@
+use crate::{kv, writer::LogWriter, Config};
+
+use super::{json::escape, Formatter};
+
+/// Formats the records as logfmt, e.g. `ts=... level=info target=app msg="Hello, world!"`.
+pub struct LogfmtFormatter<'a> {
+    record: &'a log::Record<'a>,
+    config: &'a Config,
+}
+
+impl LogfmtFormatter<'_> {
+    pub fn new<'a>(record: &'a log::Record<'a>, config: &'a Config) -> LogfmtFormatter<'a> {
+        LogfmtFormatter { record, config }
+    }
+}
+
+impl Formatter for LogfmtFormatter<'_> {
+    fn format(&self) -> String {
+        let writer = LogWriter::new(self.record, self.config);
+
+        let mut result = format!(
+            "ts={} level={} target={} msg={}",
+            value(&writer.get_datetime()),
+            writer.get_level().to_lowercase(),
+            value(&writer.get_target()),
+            value(&writer.get_args())
+        );
+
+        if let Some(pid) = writer.get_pid() {
+            result.push_str(&format!(" pid={}", pid));
+        }
+
+        if let Some(thread) = writer.get_thread() {
+            result.push_str(&format!(" thread={}", value(&thread)));
+        }
+
+        let key_values = writer.get_key_values();
+
+        if !key_values.is_empty() {
+            result.push_str(&format!(" {}", kv::format_text(&key_values)));
+        }
+
+        if let (Some(file), Some(line)) = (writer.get_file(), writer.get_line()) {
+            result.push_str(&format!(" caller={}", value(&format!("{}:{}", file, line))));
+        }
+
+        if let Some(version) = writer.get_version() {
+            result.push_str(&format!(" version={}", value(&version)));
+        }
+
+        if let Some(commit) = writer.get_commit() {
+            result.push_str(&format!(" commit={}", value(&commit)));
+        }
+
+        result
+    }
+}
+
+/// A logfmt value, quoted when it is empty or contains spaces, `=`, quotes or control characters.
+pub(crate) fn value(value: &str) -> String {
+    let quote = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '=' || c == '"');
+
+    match quote {
+        true => format!("\"{}\"", escape(value)),
+        false => value.to_string(),
+    }
+}
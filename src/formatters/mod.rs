@@ -1,25 +1,104 @@
 use log::LevelFilter;
+use std::sync::Arc;
 
-use crate::Config;
+use crate::{ColorTheme, Config, LineEnding, OverflowPolicy, RecordFormat, RetryPolicy, SepStyle};
 
 pub mod default;
+pub mod json;
+pub mod logfmt;
 pub mod readable;
+pub mod template;
 
 pub trait Formatter {
     fn format(&self) -> String;
 }
 
+/// A formatter that calls a closure, e.g. the `file_formatter` of the config.
+pub(crate) struct FnFormatter<'a> {
+    record: &'a log::Record<'a>,
+    config: &'a Config,
+    format: &'a RecordFormat,
+}
+
+impl<'a> FnFormatter<'a> {
+    pub fn new(record: &'a log::Record<'a>, config: &'a Config, format: &'a RecordFormat) -> Self {
+        FnFormatter {
+            record,
+            config,
+            format,
+        }
+    }
+}
+
+impl Formatter for FnFormatter<'_> {
+    fn format(&self) -> String {
+        (self.format)(self.record, self.config)
+    }
+}
+
 impl Config {
+    #[allow(deprecated)]
     pub fn new() -> Config {
         Config {
-            level_filter: LevelFilter::Off,
+            level_filter: LevelFilter::Trace,
             datetime_format: "%Y-%m-%d %H:%M:%S".to_string(),
+            utc_suffix: false,
             #[cfg(feature = "timezone")]
             timezone: chrono_tz::Tz::UTC,
             max_file_size: None,
+            max_rotated_files: None,
+            #[cfg(feature = "gzip")]
+            compress_rotated: false,
             retention_days: None,
+            session_separator: false,
+            max_level: None,
             levels: None,
             targets: None,
+            rules: Vec::new(),
+            excluded_targets: None,
+            #[cfg(feature = "regex")]
+            target_regexes: None,
+            footer: None,
+            flush_every: None,
+            flush_interval: None,
+            max_records: None,
+            version: None,
+            commit: None,
+            console_batch_size: None,
+            console_split_streams: false,
+            async_capacity: None,
+            non_blocking: false,
+            queue_capacity: None,
+            overflow: OverflowPolicy::Block,
+            record_separator: SepStyle::Blank,
+            line_ending: LineEnding::Lf,
+            color_theme: ColorTheme::default(),
+            force_color: None,
+            formatted_target: true,
+            target_overflow: None,
+            message_overflow: None,
+            max_message_length: None,
+            max_kv_value_length: None,
+            max_key_values: None,
+            show_thread: false,
+            show_pid: false,
+            terminal_title_on_error: false,
+            template: None,
+            json_schema: None,
+            json_promoted_keys: None,
+            source_root: None,
+            location_min_level: None,
+            default_target: None,
+            syslog_facility: 1,
+            message_transform: None,
+            file_formatter: None,
+            backtrace_on_error: false,
+            abort_on: None,
+            sample_per_message: None,
+            capture_panics: false,
+            dedup_window: None,
+            retry_policy: RetryPolicy::default(),
+            clock: Arc::new(chrono::Local::now),
         }
     }
 }
@@ -1,7 +1,54 @@
-use crate::{ansi_escape::TextStyling, writer::LogWriter, Config};
+use crate::{
+    ansi_escape::{self, GeneratedTextStyling, StyleCode, TextStyling},
+    writer::LogWriter,
+    Config,
+};
 
 use super::Formatter;
 
+/// The separator printed between records by the formatted console.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SepStyle {
+    /// An empty line.
+    #[default]
+    Blank,
+    /// No separator.
+    None,
+    /// A horizontal rule (`────────`).
+    Rule,
+    /// A custom line.
+    Custom(String),
+}
+
+/// The colors of the formatted console. The default theme uses black for the datetime and the
+/// location, which can be replaced with e.g. `bright_black` for dark terminals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorTheme {
+    pub trace: StyleCode,
+    pub debug: StyleCode,
+    pub info: StyleCode,
+    pub warn: StyleCode,
+    pub error: StyleCode,
+    /// The color of the datetime and of the `Rule` separator.
+    pub datetime: StyleCode,
+    /// The color of the target, the file and the line.
+    pub file: StyleCode,
+}
+
+impl Default for ColorTheme {
+    fn default() -> Self {
+        ColorTheme {
+            trace: StyleCode::black,
+            debug: StyleCode::blue,
+            info: StyleCode::green,
+            warn: StyleCode::yellow,
+            error: StyleCode::red,
+            datetime: StyleCode::black,
+            file: StyleCode::black,
+        }
+    }
+}
+
 pub struct ReadableFormatter<'a> {
     record: &'a log::Record<'a>,
     config: &'a Config,
@@ -19,29 +66,51 @@ impl Formatter for ReadableFormatter<'_> {
 
         let mut result = String::new();
 
-        let level = match self.record.level() {
-            log::Level::Trace => writer.get_level().bold().black(),
-            log::Level::Debug => writer.get_level().bold().blue(),
-            log::Level::Info => writer.get_level().bold().green(),
-            log::Level::Warn => writer.get_level().bold().yellow(),
-            log::Level::Error => writer.get_level().bold().red(),
+        let theme = &self.config.color_theme;
+
+        let color = match self.record.level() {
+            log::Level::Trace => theme.trace,
+            log::Level::Debug => theme.debug,
+            log::Level::Info => theme.info,
+            log::Level::Warn => theme.warn,
+            log::Level::Error => theme.error,
         };
 
-        result.push_str(&format!("{} · {}\n", writer.get_datetime().black(), level));
-        result.push_str(&format!("{}\n", writer.get_args().bold()));
+        result.push_str(&format!(
+            "{} · {}",
+            writer.get_datetime().style(theme.datetime),
+            writer.get_level().bold().style(color)
+        ));
+
+        if self.config.formatted_target {
+            result.push_str(&format!(" · {}", writer.get_target().style(theme.file)));
+        }
 
-        let file = writer.get_file();
-        let line = writer.get_line();
+        result.push('\n');
+        result.push_str(&format!("{}\n", writer.get_args().bold()));
 
-        if file.is_some() && line.is_some() {
+        if let (Some(file), Some(line)) = (writer.get_file(), writer.get_line()) {
             result.push_str(&format!(
                 "{}{}{}\n",
-                file.unwrap().black(),
-                ":".black(),
-                line.unwrap().black()
+                file.style(theme.file),
+                ":".style(theme.file),
+                line.style(theme.file)
             ));
         }
 
+        match &self.config.record_separator {
+            SepStyle::Blank => {}
+            SepStyle::None => {
+                result.pop();
+            }
+            SepStyle::Rule => result.push_str(&"─".repeat(40).style(theme.datetime).to_string()),
+            SepStyle::Custom(separator) => result.push_str(separator),
+        }
+
+        if self.config.force_color == Some(false) {
+            return ansi_escape::strip(&result);
+        }
+
         result
     }
 }
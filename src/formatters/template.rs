@@ -0,0 +1,87 @@
+use crate::{formatters::Config, helpers::syslog_severity, writer::LogWriter};
+
+use super::Formatter;
+
+/// Formats records with the `template` of the config, replacing the `{token}`s with the fields of
+/// the record. Unknown tokens are written as is.
+pub struct TemplateFormatter<'a> {
+    record: &'a log::Record<'a>,
+    config: &'a Config,
+    line_no: Option<u64>,
+}
+
+impl TemplateFormatter<'_> {
+    pub fn new<'a>(record: &'a log::Record<'a>, config: &'a Config) -> TemplateFormatter<'a> {
+        TemplateFormatter {
+            record,
+            config,
+            line_no: None,
+        }
+    }
+
+    /// Set the number of the line in the file, for the `{line_no}` token.
+    pub fn with_line_no(mut self, line_no: Option<u64>) -> Self {
+        self.line_no = line_no;
+
+        self
+    }
+
+    fn token(&self, writer: &LogWriter, name: &str) -> Option<String> {
+        let value = match name {
+            "datetime" => writer.get_datetime(),
+            "level" => writer.get_level(),
+            "target" => writer.get_target(),
+            "crate" => writer.get_crate(),
+            "message" => writer.get_args(),
+            "file" => writer.get_file().unwrap_or_default(),
+            "line" => writer
+                .get_line()
+                .map(|line| line.to_string())
+                .unwrap_or_default(),
+            "line_no" => self
+                .line_no
+                .map(|line_no| line_no.to_string())
+                .unwrap_or_default(),
+            "version" => writer.get_version().unwrap_or_default(),
+            "commit" => writer.get_commit().unwrap_or_default(),
+            "priority" => {
+                let facility = self.config.syslog_facility as u16;
+
+                (facility * 8 + syslog_severity(self.record.level()) as u16).to_string()
+            }
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+impl Formatter for TemplateFormatter<'_> {
+    fn format(&self) -> String {
+        let writer = LogWriter::new(self.record, self.config);
+        let template = self.config.template.as_deref().unwrap_or_default();
+
+        let mut result = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+
+            match self.token(&writer, &rest[1..end]) {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(&rest[..=end]),
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        result.push_str(rest);
+
+        result
+    }
+}
@@ -0,0 +1,200 @@
+use log::Log;
+use std::{
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc, RwLock},
+};
+
+use crate::{
+    error::FtailError, helpers::CurrentFile, spawn_flush_timer, with_state, Config, Ftail,
+    LogChannel, LoggerState,
+};
+
+/// A handle to change the logger after it has been initialized.
+#[derive(Clone)]
+pub struct FtailHandle {
+    state: Arc<RwLock<LoggerState>>,
+    installed: bool,
+}
+
+impl FtailHandle {
+    pub(crate) fn new(state: Arc<RwLock<LoggerState>>) -> Self {
+        FtailHandle {
+            state,
+            installed: false,
+        }
+    }
+
+    /// The handle of the logger installed by `init`, which keeps `log::max_level` in line with
+    /// the channels.
+    pub(crate) fn installed(mut self) -> Self {
+        self.installed = true;
+
+        self
+    }
+
+    fn update_max_level(&self, state: &LoggerState) {
+        if self.installed {
+            log::set_max_level(state.max_level());
+        }
+    }
+
+    /// Add a channel to the initialized logger. The channel uses the configuration of the logger,
+    /// e.g. `async_channels`, and its failed writes are returned by `close`.
+    pub fn add_channel_dynamic<F>(&self, constructor: F, level: log::LevelFilter)
+    where
+        F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
+    {
+        let mut state = self.state.write().unwrap();
+
+        let channel = LogChannel::new(constructor, level);
+        let channel = state
+            .write_errors
+            .collect(|| channel.init(state.config.clone(), state.writer.as_ref()));
+        state.channels.push(channel);
+
+        self.update_max_level(&state);
+    }
+
+    /// Set the most verbose level logged by every channel, replacing their own levels and
+    /// `max_level`, e.g. to log the debug records of a running service. The rules and level
+    /// filters of the logger still apply.
+    pub fn set_level(&self, level: log::LevelFilter) {
+        let state = self.state.read().unwrap();
+        state.set_runtime_level(level);
+
+        self.update_max_level(&state);
+    }
+
+    /// Only log the records with the targets, in addition to the other filters of the logger,
+    /// e.g. its `Deny` rules. An empty list logs all the targets allowed by the filters again.
+    pub fn set_target_filter(&self, targets: Vec<&str>) {
+        self.state.write().unwrap().target_filter =
+            (!targets.is_empty()).then(|| targets.into_iter().map(str::to_string).collect());
+    }
+
+    /// Enable or disable the channels with the name given by `named`.
+    /// Returns false when there is no channel with the name.
+    pub fn set_channel_enabled(&self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+
+        for channel in &self.state.read().unwrap().channels {
+            if channel.name.as_deref() == Some(name) {
+                channel.enabled.store(enabled, Ordering::Relaxed);
+                found = true;
+            }
+        }
+
+        found
+    }
+
+    /// Flush the channels with the name given by `named`, e.g. before a checkpoint, without
+    /// flushing the others. Returns false when there is no channel with the name.
+    pub fn flush_channel(&self, name: &str) -> bool {
+        let mut found = false;
+
+        for channel in &self.state.read().unwrap().channels {
+            if channel.name.as_deref() == Some(name) {
+                channel.channel.flush();
+                found = true;
+            }
+        }
+
+        found
+    }
+
+    /// The path of the file the file channel with the name given by `named` is writing to, e.g.
+    /// today's file of a daily file channel. None when there is no file channel with the name, or
+    /// when its path is unknown (`file_handle`).
+    pub fn current_path(&self, name: &str) -> Option<PathBuf> {
+        self.current_file(name, |file| file.path.read().unwrap().clone())
+    }
+
+    /// The size in bytes of the file the file channel with the name given by `named` is writing
+    /// to, including the records that are not flushed yet.
+    pub fn current_size(&self, name: &str) -> Option<u64> {
+        self.current_file(name, |file| Some(file.size.load(Ordering::Acquire)))
+    }
+
+    /// Close the current file of the file channel with the name given by `named` and continue in a
+    /// fresh one, e.g. before collecting a snapshot of the logs: a single file is renamed to
+    /// `<path>.old` and a daily file continues in the next file of the day. Returns false when
+    /// there is no file channel with the name, or when it cannot be rotated (`file_handle`).
+    pub fn rotate_now(&self, name: &str) -> bool {
+        let mut found = false;
+
+        for channel in &self.state.read().unwrap().channels {
+            let Some(current_file) = &channel.current_file else {
+                continue;
+            };
+
+            if channel.name.as_deref() != Some(name) || current_file.path.read().unwrap().is_none()
+            {
+                continue;
+            }
+
+            // the channel rotates at its next record or flush, so flush it to rotate now
+            current_file.rotate.store(true, Ordering::Release);
+            channel.channel.flush();
+            found = true;
+        }
+
+        found
+    }
+
+    fn current_file<T>(&self, name: &str, get: impl Fn(&CurrentFile) -> Option<T>) -> Option<T> {
+        self.state
+            .read()
+            .unwrap()
+            .channels
+            .iter()
+            .filter(|channel| channel.name.as_deref() == Some(name))
+            .find_map(|channel| channel.current_file.as_deref().and_then(&get))
+    }
+
+    /// Replace the channels and configuration of the logger with the ones of a new logger, e.g. to
+    /// reload the configuration. The old channels are flushed and dropped, so their footers are written.
+    /// The failed writes of the old channels are still returned by `close`.
+    pub fn reconfigure(&self, ftail: Ftail) -> Result<(), FtailError> {
+        let write_errors = self.state.read().unwrap().write_errors.clone();
+        let new_state = ftail.build_with(write_errors)?.into_state();
+
+        let mut state = self.state.write().unwrap();
+        state.flush();
+
+        let old_state = std::mem::replace(&mut *state, new_state);
+        self.update_max_level(&state);
+        drop(state);
+        drop(old_state);
+
+        spawn_flush_timer(&self.state)
+    }
+
+    /// Log a record through the filters and channels of the logger, as the `log` macros do, e.g. to
+    /// bridge records from another log source.
+    pub fn log_record(&self, record: &log::Record) {
+        with_state(&self.state, |state| state.log(record));
+    }
+
+    /// Flush and drop the channels, waiting for the threads of async channels, stop the flush
+    /// timer and return the failed writes of the session. The logger logs nothing once it is
+    /// closed.
+    pub fn close(&self) -> Result<(), FtailError> {
+        let mut state = self.state.write().unwrap();
+        state.flush();
+
+        let channels = std::mem::take(&mut state.channels);
+        let writer = state.writer.take();
+        // replacing the token stops the flush timer
+        state.flush_timer = Arc::default();
+        let write_errors = state.write_errors.clone();
+        self.update_max_level(&state);
+        drop(state);
+        drop(channels);
+        drop(writer);
+
+        match write_errors.take() {
+            Some((count, error)) => Err(FtailError::WriteErrors(count, error)),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,34 +1,654 @@
-use std::{fs::File, io::LineWriter, path::PathBuf, sync::Mutex};
+use log::{Level, LevelFilter};
+use std::{
+    backtrace::Backtrace,
+    fs::File,
+    io::{BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, RwLock,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::Config;
+use crate::{
+    error::FtailError,
+    formatters::{default::DefaultFormatter, FnFormatter, Formatter},
+    writer::LogWriter,
+    ChannelStats, Config, RenderedRecord, WriteErrors,
+};
 
+pub(crate) struct RecordCounter {
+    started_at: Instant,
+    records: AtomicU64,
+    warnings: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl RecordCounter {
+    pub fn new() -> Self {
+        RecordCounter {
+            started_at: Instant::now(),
+            records: AtomicU64::new(0),
+            warnings: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    pub fn count(&self, level: Level) -> u64 {
+        match level {
+            Level::Warn => self.warnings.fetch_add(1, Ordering::Relaxed),
+            Level::Error => self.errors.fetch_add(1, Ordering::Relaxed),
+            _ => 0,
+        };
+
+        self.records.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            records: self.records.load(Ordering::Relaxed),
+            warnings: self.warnings.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            duration: self.started_at.elapsed(),
+        }
+    }
+}
+
+/// The syslog severity of the level, as used by syslog and GELF.
+pub(crate) fn syslog_severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug => 7,
+        Level::Trace => 7,
+    }
+}
+
+/// Lock the mutex of a file, recovering it when a thread panicked while holding it, so one
+/// panic does not make every later logging call panic as well.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The name of the host, for the network channels.
+#[cfg(any(feature = "gelf", feature = "syslog"))]
+pub(crate) fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+pub(crate) fn should_flush(records: u64, level: Level, config: &Config) -> bool {
+    match (config.flush_every, config.flush_interval) {
+        (Some(n), _) => level <= Level::Warn || records % n == 0,
+        (None, Some(_)) => level <= Level::Warn,
+        (None, None) => true,
+    }
+}
+
+/// Format the record with the formatter of the channel, passing the line through the message transform when set.
+pub(crate) fn format_message<F: Formatter>(
+    record: &log::Record,
+    config: &Config,
+    formatter: F,
+) -> String {
+    let message = formatter.format();
+
+    match &config.message_transform {
+        Some(transform) => transform(
+            record,
+            &RenderedRecord::with_formatted(record, config, message),
+        ),
+        None => message,
+    }
+}
+
+/// Format a record for the single and daily file channels, with the `file_formatter` when set.
+pub(crate) fn format_file_message(
+    record: &log::Record,
+    config: &Config,
+    line_no: Option<u64>,
+) -> String {
+    match &config.file_formatter {
+        Some(format) => format_message(record, config, FnFormatter::new(record, config, format)),
+        None => format_message(
+            record,
+            config,
+            DefaultFormatter::new(record, config).with_line_no(line_no),
+        ),
+    }
+}
+
+/// Append the current backtrace, indented, to error messages when `backtrace_on_error` is set.
+/// The backtrace is only printed in full when `RUST_BACKTRACE=full`.
+pub(crate) fn append_backtrace(message: &mut String, level: Level, config: &Config) {
+    if !config.backtrace_on_error || level != Level::Error {
+        return;
+    }
+
+    let backtrace = Backtrace::force_capture();
+
+    let backtrace = match std::env::var("RUST_BACKTRACE").as_deref() {
+        Ok("full") => format!("{:#}", backtrace),
+        _ => format!("{}", backtrace),
+    };
+
+    for line in backtrace.lines() {
+        message.push_str(&format!("\n    {}", line));
+    }
+}
+
+/// The terminator the file channels write after each record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`, e.g. for the tools on Windows that show `\n` files on a single line.
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Write a line terminated with the line ending of the configuration.
+pub(crate) fn write_line<W: Write>(
+    writer: &mut W,
+    line: &str,
+    config: &Config,
+) -> std::io::Result<()> {
+    write!(writer, "{}{}", line, config.line_ending.as_str())
+}
+
+/// The number of bytes written by `write_line` for the line.
+pub(crate) fn line_size(line: &str, config: &Config) -> u64 {
+    (line.len() + config.line_ending.as_str().len()) as u64
+}
+
+/// Keep the error of a failed write, to report it when the logger is closed.
+pub(crate) fn track_write<T>(result: std::io::Result<T>, write_errors: &WriteErrors) -> bool {
+    match result {
+        Ok(_) => true,
+        Err(error) => {
+            write_errors.push(error);
+
+            false
+        }
+    }
+}
+
+pub(crate) fn write_footer<W: Write>(
+    file: &mut W,
+    counter: &RecordCounter,
+    config: &Config,
+    write_errors: &WriteErrors,
+) {
+    if let Some(footer) = &config.footer {
+        track_write(
+            write_line(file, &footer(&counter.stats()), config),
+            write_errors,
+        );
+        track_write(file.flush(), write_errors);
+    }
+}
+
+/// Rotate the file when it exceeds `max_file_size`. The size is tracked in `size` so the file
+/// is only locked when it has to be rotated.
 pub(crate) fn rotate_if_exceeds_max_file_size(
-    file: &Mutex<LineWriter<File>>,
+    file: &Mutex<BufWriter<File>>,
     file_path: PathBuf,
+    size: &AtomicU64,
+    line_numbers: &LineNumbers,
+    config: &Config,
+    write_errors: &WriteErrors,
+) {
+    let Some(max_file_size) = config.max_file_size else {
+        return;
+    };
+
+    if size.load(Ordering::Acquire) <= max_file_size {
+        return;
+    }
+
+    let mut file = lock(file);
+
+    // another thread rotated the file while we were waiting for the lock
+    if size.load(Ordering::Acquire) <= max_file_size {
+        return;
+    }
+
+    // keep writing to the current file when it cannot be rotated, e.g. its directory was removed
+    let rotated = rotate_file(&mut file, &file_path, size, line_numbers);
+    drop(file);
+
+    match rotated {
+        Ok(rotated) => finish_rotation(&file_path, &rotated, config, write_errors),
+        Err(error) => write_errors.push(error),
+    }
+}
+
+/// Rename the file to `<path>.old`, or `<path>.oldN` after the newest rotated file, and continue
+/// in a new file. Returns the path of the rotated file.
+pub(crate) fn rotate_file(
+    file: &mut BufWriter<File>,
+    file_path: &Path,
+    size: &AtomicU64,
+    line_numbers: &LineNumbers,
+) -> std::io::Result<PathBuf> {
+    let path = file_path.to_string_lossy();
+
+    // the rotated files are never renamed again, so a crash at any point keeps every file
+    let new_path = match rotated_files(file_path).last() {
+        Some((index, _)) => format!("{}.old{}", path, index + 1),
+        None => format!("{}.old", path),
+    };
+
+    file.flush()?;
+    std::fs::rename(file_path, &new_path)?;
+
+    let new_file = std::fs::File::create(file_path)?;
+    *file = BufWriter::new(new_file);
+
+    size.store(0, Ordering::Release);
+    line_numbers.reset();
+
+    Ok(PathBuf::from(new_path))
+}
+
+/// Compress the rotated file and remove the oldest rotated files beyond `max_rotated_files`. It
+/// does not touch the live file, so it is called after its lock is released.
+pub(crate) fn finish_rotation(
+    file_path: &Path,
+    rotated: &Path,
     config: &Config,
+    write_errors: &WriteErrors,
 ) {
-    if config.max_file_size.is_none() {
+    compress_rotated(rotated, config, write_errors);
+
+    let Some(max_rotated_files) = config.max_rotated_files else {
         return;
+    };
+
+    let rotated = rotated_files(file_path);
+    let excess = rotated.len().saturating_sub(max_rotated_files);
+
+    for (_, path) in rotated.into_iter().take(excess) {
+        track_write(std::fs::remove_file(path), write_errors);
     }
+}
+
+/// The files rotated from the path, `<path>.old` and `<path>.oldN`, compressed or not, from the
+/// oldest to the newest.
+fn rotated_files(file_path: &Path) -> Vec<(u64, PathBuf)> {
+    let (Some(name), Some(dir)) = (file_path.file_name(), file_path.parent()) else {
+        return Vec::new();
+    };
+
+    let dir = match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    };
 
-    let mut file = file.lock().unwrap();
+    let prefix = format!("{}.old", name.to_string_lossy());
 
-    let md = file.get_ref().metadata().unwrap();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
 
-    if md.len() > config.max_file_size.unwrap() {
-        let path = file_path.to_str().unwrap();
+    let mut files = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name();
+            let file_name = file_name.to_str()?;
 
-        let mut new_path = format!("{}.old", path);
+            let index = file_name.strip_prefix(&prefix)?;
+            let index = index.strip_suffix(".gz").unwrap_or(index);
 
-        let mut counter = 1;
-        while std::fs::metadata(&new_path).is_ok() {
-            new_path = format!("{}.old{}", path, counter);
-            counter += 1;
+            let index = match index.is_empty() {
+                true => 0,
+                false if index.bytes().all(|byte| byte.is_ascii_digit()) => index.parse().ok()?,
+                false => return None,
+            };
+
+            Some((index, entry.path()))
+        })
+        .collect::<Vec<_>>();
+
+    files.sort();
+
+    files
+}
+
+/// Gzip a rotated file to `<path>.gz` when `compress_rotated` is set.
+#[cfg_attr(not(feature = "gzip"), allow(unused_variables))]
+fn compress_rotated(path: &Path, config: &Config, write_errors: &WriteErrors) {
+    #[cfg(feature = "gzip")]
+    if config.compress_rotated {
+        track_write(gzip(path), write_errors);
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(path: &Path) -> std::io::Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+
+    let mut encoder = flate2::write::GzEncoder::new(
+        BufWriter::new(File::create(&gz_path)?),
+        flate2::Compression::default(),
+    );
+
+    std::io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.flush()?;
+
+    std::fs::remove_file(path)
+}
+
+/// The file a file channel is writing to, shared with the handle to query its path and size and
+/// to request a rotation.
+#[derive(Default)]
+pub(crate) struct CurrentFile {
+    pub path: RwLock<Option<PathBuf>>,
+    pub size: AtomicU64,
+    pub rotate: AtomicBool,
+}
+
+impl CurrentFile {
+    pub fn new(path: Option<PathBuf>, size: u64) -> Arc<Self> {
+        Arc::new(CurrentFile {
+            path: RwLock::new(path),
+            size: AtomicU64::new(size),
+            rotate: AtomicBool::new(false),
+        })
+    }
+
+    /// Take the path and size of another file, to share this one instead.
+    pub fn replace(&self, other: &CurrentFile) {
+        *self.path.write().unwrap() = other.path.read().unwrap().clone();
+        self.size
+            .store(other.size.load(Ordering::Acquire), Ordering::Release);
+    }
+}
+
+/// Numbers the lines written to a file for the `{line_no}` token of the template, from 1 in each
+/// session and again after each rotation of the file.
+pub(crate) struct LineNumbers {
+    enabled: bool,
+    last: AtomicU64,
+}
+
+impl LineNumbers {
+    pub fn new(config: &Config) -> Self {
+        LineNumbers {
+            enabled: config
+                .template
+                .as_deref()
+                .is_some_and(|template| template.contains("{line_no}")),
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// Lock the file and format the line. When the template uses `{line_no}`, the line is
+    /// formatted under the lock, so the numbers follow the order of the lines in the file.
+    pub fn lock_and_format<'a, T>(
+        &self,
+        file: &'a Mutex<T>,
+        format: impl FnOnce(Option<u64>) -> String,
+    ) -> (MutexGuard<'a, T>, String, Option<u64>) {
+        self.lock_rotate_and_format(file, |_| {}, format)
+    }
+
+    /// Like `lock_and_format`, rotating the file under the same lock before the line is numbered
+    /// and written, so the line is never written to a file that is being replaced.
+    pub fn lock_rotate_and_format<'a, T>(
+        &self,
+        file: &'a Mutex<T>,
+        rotate: impl FnOnce(&mut T),
+        format: impl FnOnce(Option<u64>) -> String,
+    ) -> (MutexGuard<'a, T>, String, Option<u64>) {
+        if !self.enabled {
+            let line = format(None);
+
+            let mut file = lock(file);
+            rotate(&mut file);
+
+            return (file, line, None);
+        }
+
+        let mut file = lock(file);
+        rotate(&mut file);
+
+        let line_no = self.last.load(Ordering::Acquire) + 1;
+
+        (file, format(Some(line_no)), Some(line_no))
+    }
+
+    /// Count the line once it is written, so a failed write does not skip a number.
+    pub fn written(&self, line_no: Option<u64>) {
+        if let Some(line_no) = line_no {
+            self.last.store(line_no, Ordering::Release);
+        }
+    }
+
+    pub fn reset(&self) {
+        self.last.store(0, Ordering::Release);
+    }
+}
+
+static CONSOLE_BUFFERS: Mutex<Vec<Arc<Mutex<Vec<String>>>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static CONSOLE_BUFFER: ConsoleBuffer = ConsoleBuffer::register();
+}
+
+struct ConsoleBuffer(Arc<Mutex<Vec<String>>>);
+
+impl ConsoleBuffer {
+    fn register() -> Self {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+
+        CONSOLE_BUFFERS.lock().unwrap().push(Arc::clone(&lines));
+
+        ConsoleBuffer(lines)
+    }
+}
+
+impl Drop for ConsoleBuffer {
+    fn drop(&mut self) {
+        if let Ok(mut lines) = self.0.lock() {
+            write_console_lines(&mut lines);
         }
 
-        std::fs::rename(path, &new_path).unwrap();
+        if let Ok(mut buffers) = CONSOLE_BUFFERS.lock() {
+            buffers.retain(|lines| !Arc::ptr_eq(lines, &self.0));
+        }
+    }
+}
+
+/// The level and time of the last terminal title set by `terminal_title_on_error`.
+static TERMINAL_TITLE: Mutex<Option<(Level, Instant)>> = Mutex::new(None);
+
+/// How long a terminal title is kept before a less severe record may replace it.
+const TERMINAL_TITLE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Whether escape codes can be written to the standard output: forced on or off with
+/// `force_color`, and otherwise only when `NO_COLOR` is not set and stdout is a terminal.
+pub(crate) fn stdout_supports_escapes(config: &Config) -> bool {
+    supports_escapes(config, std::io::stdout().is_terminal())
+}
+
+/// Whether escape codes can be written to the standard error, as for the standard output.
+pub(crate) fn stderr_supports_escapes(config: &Config) -> bool {
+    supports_escapes(config, std::io::stderr().is_terminal())
+}
 
-        let new_file = std::fs::File::create(path).unwrap();
-        *file = LineWriter::new(new_file);
+fn supports_escapes(config: &Config, terminal: bool) -> bool {
+    match config.force_color {
+        Some(color) => color,
+        None => std::env::var_os("NO_COLOR").map_or(true, |value| value.is_empty()) && terminal,
     }
 }
+
+/// Prefix the console line with an OSC escape setting the terminal title when the record is a
+/// warning or an error, unless a more severe record set the title within the last minute.
+pub(crate) fn prefix_terminal_title(line: &mut String, record: &log::Record, config: &Config) {
+    if !config.terminal_title_on_error
+        || record.level() > Level::Warn
+        || !stdout_supports_escapes(config)
+    {
+        return;
+    }
+
+    let mut title = TERMINAL_TITLE.lock().unwrap();
+
+    if let Some((level, at)) = *title {
+        if level < record.level() && at.elapsed() < TERMINAL_TITLE_WINDOW {
+            return;
+        }
+    }
+
+    *title = Some((record.level(), Instant::now()));
+
+    line.insert_str(
+        0,
+        &format!(
+            "\x1b]0;{} · {}\x07",
+            record.level(),
+            LogWriter::new(record, config).get_target()
+        ),
+    );
+}
+
+/// Print a line to the standard output, or to the standard error for warnings and errors with
+/// `console_split_streams`. When `console_batch_size` is set, the stdout lines are buffered per
+/// thread and the buffer is written under a single stdout lock once it is full, at a warning or an
+/// error, and when the channels are flushed, e.g. by the `flush_interval` timer.
+pub(crate) fn print_console_line(line: String, level: Level, config: &Config) {
+    if config.console_split_streams && level <= Level::Warn {
+        // the lines buffered before the warning are written first to keep them in order
+        if config.console_batch_size.is_some() {
+            let _ = CONSOLE_BUFFER.try_with(|buffer| write_console_lines(&mut lock(&buffer.0)));
+        }
+
+        eprintln!("{}", line);
+
+        return;
+    }
+
+    let Some(batch_size) = config.console_batch_size else {
+        println!("{}", line);
+
+        return;
+    };
+
+    let mut line = Some(line);
+
+    let _ = CONSOLE_BUFFER.try_with(|buffer| {
+        let mut lines = buffer.0.lock().unwrap();
+
+        lines.extend(line.take());
+
+        if lines.len() >= batch_size || level <= Level::Warn {
+            write_console_lines(&mut lines);
+        }
+    });
+
+    // the buffer of this thread is already destroyed
+    if let Some(line) = line {
+        println!("{}", line);
+    }
+}
+
+pub(crate) fn flush_console_lines() {
+    for lines in CONSOLE_BUFFERS.lock().unwrap().iter() {
+        write_console_lines(&mut lines.lock().unwrap());
+    }
+}
+
+fn write_console_lines(lines: &mut Vec<String>) {
+    if lines.is_empty() {
+        return;
+    }
+
+    let mut batch = lines.join("\n");
+    lines.clear();
+    batch.push('\n');
+
+    let mut stdout = std::io::stdout().lock();
+
+    let _ = stdout.write_all(batch.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Parse a size with a unit, e.g. `100MB`, into bytes. The units are `B`, `KB`, `MB` and `GB`
+/// (powers of 1024), in any case.
+pub(crate) fn parse_size(size: &str) -> Result<u64, FtailError> {
+    let (value, unit) = split_unit(size)?;
+
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1024,
+        "MB" => 1024 * 1024,
+        "GB" => 1024 * 1024 * 1024,
+        _ => return Err(FtailError::InvalidSize(size.to_string())),
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| FtailError::InvalidSize(size.to_string()))
+}
+
+/// Parse a retention with a unit, e.g. `2w`, into days. The units are `d` (the default), `w`
+/// and `m` (30 days), in any case.
+pub(crate) fn parse_retention_days(retention: &str) -> Result<u64, FtailError> {
+    let (value, unit) = split_unit(retention)?;
+
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "" | "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        _ => return Err(FtailError::InvalidSize(retention.to_string())),
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| FtailError::InvalidSize(retention.to_string()))
+}
+
+/// Parse a level filter, in any case, with the aliases `err`, `warning` and `verbose` (trace), or
+/// a number from `0` (off) to `5` (trace).
+pub(crate) fn parse_level_filter(level: &str) -> Option<LevelFilter> {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "off" | "0" => Some(LevelFilter::Off),
+        "error" | "err" | "1" => Some(LevelFilter::Error),
+        "warn" | "warning" | "2" => Some(LevelFilter::Warn),
+        "info" | "3" => Some(LevelFilter::Info),
+        "debug" | "4" => Some(LevelFilter::Debug),
+        "trace" | "verbose" | "5" => Some(LevelFilter::Trace),
+        _ => None,
+    }
+}
+
+fn split_unit(text: &str) -> Result<(u64, &str), FtailError> {
+    let text = text.trim();
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+
+    let value = text[..digits]
+        .parse()
+        .map_err(|_| FtailError::InvalidSize(text.to_string()))?;
+
+    Ok((value, text[digits..].trim_start()))
+}
@@ -0,0 +1,175 @@
+//! The structured key-values of the records, e.g. `log::info!(user_id = 42; "login")`. They are
+//! only collected with the `kv` feature, which enables the key-values of the `log` crate.
+
+use crate::{
+    formatters::{json::escape, logfmt::value},
+    writer::TRUNCATED_MARKER,
+    Config,
+};
+
+/// A key-value of a record, owned so it can be sent to another thread with the record.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "kv"), allow(dead_code))]
+pub(crate) struct KeyValue {
+    pub key: String,
+    pub value: KvValue,
+}
+
+/// The value of a key-value. Numbers and booleans are kept apart from the other values, so they
+/// are written without quotes in JSON.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "kv"), allow(dead_code))]
+pub(crate) enum KvValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+}
+
+impl std::fmt::Display for KvValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KvValue::Bool(value) => write!(f, "{}", value),
+            KvValue::I64(value) => write!(f, "{}", value),
+            KvValue::U64(value) => write!(f, "{}", value),
+            KvValue::F64(value) => write!(f, "{}", value),
+            KvValue::Str(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl KvValue {
+    fn to_json(&self) -> String {
+        match self {
+            KvValue::Bool(_) | KvValue::I64(_) | KvValue::U64(_) => self.to_string(),
+            KvValue::F64(value) if value.is_finite() => self.to_string(),
+            _ => format!("\"{}\"", escape(&self.to_string())),
+        }
+    }
+}
+
+/// The key-values of the record, in the order they were logged.
+#[cfg(feature = "kv")]
+pub(crate) fn collect(record: &log::Record) -> Vec<KeyValue> {
+    let mut collector = Collector(Vec::new());
+    let _ = record.key_values().visit(&mut collector);
+
+    collector.0
+}
+
+#[cfg(not(feature = "kv"))]
+pub(crate) fn collect(_record: &log::Record) -> Vec<KeyValue> {
+    Vec::new()
+}
+
+/// Keep the first `max_key_values` key-values, and cut the values longer than
+/// `max_kv_value_length` characters, marked with `TRUNCATED_MARKER`.
+pub(crate) fn limit(mut key_values: Vec<KeyValue>, config: &Config) -> Vec<KeyValue> {
+    if let Some(max_key_values) = config.max_key_values {
+        key_values.truncate(max_key_values);
+    }
+
+    if let Some(max_length) = config.max_kv_value_length {
+        for kv in &mut key_values {
+            let value = kv.value.to_string();
+
+            if let Some((index, _)) = value.char_indices().nth(max_length) {
+                kv.value = KvValue::Str(format!("{}{}", &value[..index], TRUNCATED_MARKER));
+            }
+        }
+    }
+
+    key_values
+}
+
+/// The key-values as `key=value` pairs separated by spaces, with the values quoted as in logfmt.
+pub(crate) fn format_text(key_values: &[KeyValue]) -> String {
+    key_values
+        .iter()
+        .map(|kv| format!("{}={}", kv.key, value(&kv.value.to_string())))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The key-values as a JSON object.
+pub(crate) fn format_json(key_values: &[KeyValue]) -> String {
+    format!("{{{}}}", format_json_members(key_values))
+}
+
+/// The key-values as the comma-separated members of a JSON object.
+pub(crate) fn format_json_members(key_values: &[KeyValue]) -> String {
+    key_values
+        .iter()
+        .map(|kv| format!("\"{}\":{}", escape(&kv.key), kv.value.to_json()))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Split the key-values into the first one of each of the `keys`, in the order of the `keys`,
+/// and the others, in the order they were logged.
+pub(crate) fn promote<'a>(
+    mut key_values: Vec<KeyValue>,
+    keys: impl IntoIterator<Item = &'a String>,
+) -> (Vec<KeyValue>, Vec<KeyValue>) {
+    let promoted = keys
+        .into_iter()
+        .filter_map(|key| {
+            let index = key_values.iter().position(|kv| &kv.key == key)?;
+
+            Some(key_values.remove(index))
+        })
+        .collect();
+
+    (promoted, key_values)
+}
+
+#[cfg(feature = "kv")]
+struct Collector(Vec<KeyValue>);
+
+#[cfg(feature = "kv")]
+impl<'kvs> log::kv::VisitSource<'kvs> for Collector {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        let value = if let Some(value) = value.to_bool() {
+            KvValue::Bool(value)
+        } else if let Some(value) = value.to_i64() {
+            KvValue::I64(value)
+        } else if let Some(value) = value.to_u64() {
+            KvValue::U64(value)
+        } else if let Some(value) = value.to_f64() {
+            KvValue::F64(value)
+        } else {
+            KvValue::Str(value.to_string())
+        };
+
+        self.0.push(KeyValue {
+            key: key.to_string(),
+            value,
+        });
+
+        Ok(())
+    }
+}
+
+/// Give the key-values back to a record rebuilt on another thread.
+#[cfg(feature = "kv")]
+impl log::kv::Source for KeyValue {
+    fn visit<'kvs>(
+        &'kvs self,
+        visitor: &mut dyn log::kv::VisitSource<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        let value = match &self.value {
+            KvValue::Bool(value) => log::kv::Value::from(*value),
+            KvValue::I64(value) => log::kv::Value::from(*value),
+            KvValue::U64(value) => log::kv::Value::from(*value),
+            KvValue::F64(value) => log::kv::Value::from(*value),
+            KvValue::Str(value) => log::kv::Value::from(value.as_str()),
+        };
+
+        visitor.visit_pair(log::kv::Key::from_str(&self.key), value)
+    }
+}
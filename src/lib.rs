@@ -4,8 +4,26 @@
 //!
 //! - [Console](#console)
 //! - [Formatted console](#formatted-console)
+//! - [Console JSON](#console-json)
+//! - [Console logfmt](#console-logfmt)
 //! - [Single file](#single-file)
+//! - [Dual file](#dual-file)
+//! - [File handle](#file-handle)
+//! - [Writer](#writer)
+//! - [Shared file](#shared-file)
 //! - [Daily file](#daily-file)
+//! - [Hourly file](#hourly-file)
+//! - [Striped files](#striped-files)
+//! - [Elasticsearch](#elasticsearch)
+//! - [GELF](#gelf)
+//! - [SQLite](#sqlite)
+//! - [Syslog](#syslog)
+//! - [TCP](#tcp)
+//! - [Debug output](#debug-output)
+//! - [Mpsc](#mpsc)
+//! - [Broadcast](#broadcast)
+//! - [Memory](#memory)
+//! - [Callback](#callback)
 //! - [Custom channel](#custom-channel)
 //!
 //! ## Usage
@@ -36,17 +54,106 @@
 //! log::error!("This is an error message");
 //! ```
 //!
+//! Or start from a preset and tweak it further:
+//!
+//! - `Ftail::dev()` logs formatted and colored messages with their location to the console from the debug level
+//! - `Ftail::prod()` logs JSON to daily files in the `logs` directory from the info level, kept for 30 days
+//! - `Ftail::from_env()?` reads `FTAIL_LEVEL` (`off`, `error`, `warn`, `info`, `debug`, `trace`, in any case, with the aliases `err`, `warning` and `verbose`, or `0` to `5`), `FTAIL_LEVELS` and `FTAIL_TARGETS` (comma-separated) and `FTAIL_DATETIME_FORMAT`, and returns `FtailError::EnvParseError` for a bad value (the builder methods called afterwards take precedence)
+//!
+//! ```rust
+//! Ftail::prod()
+//!     .console(LevelFilter::Error)
+//!     .init()?;
+//! ```
+//!
 //! You can set the following configuration options:
 //!
 //! - `.datetime_format("%Y-%m-%d %H:%M:%S.3f")` to set the datetime format
+//! - `.with_datetime_format("%H:%M:%S")` after a channel to set its datetime format instead of the one of the logger, e.g. a short time on the console and the full date and milliseconds in a file
+//! - `.time_12h()`, `.time_24h()` or `.time_iso()` to set a common datetime format
+//! - `.template("<{priority}>{datetime} {level} {target} {message}")` to set the format of the console and file channels, with the tokens `{datetime}`, `{level}`, `{target}`, `{crate}` (the first segment of the module path), `{message}`, `{file}`, `{line}`, `{line_no}` (the number of the line in the single or daily file, from 1 in each session and after each rotation), `{version}`, `{commit}` and `{priority}` (the syslog priority, `facility * 8 + severity`)
+//! - `.syslog_facility(16)` to set the syslog facility of the `{priority}` token (`1`, user, by default)
 //! - `.timezone(ftail::Tz::UTC)` to set the timezone [requires feature `timezone`]
-//! - `.max_file_size(100)` to set the maximum file size in MB (will move older logs to .old{N})
-//! - `.retention_days(7)` to set the number of days to keep the log files (daily file only)
+//! - `.utc_suffix()` to append `Z` to the datetime when it is in UTC and the format does not include the offset (e.g. `%:z`)
+//! - `.max_file_size(100)` to set the maximum file size in MB (will move older logs to .old{N}, the daily file continues in `YYYY-MM-DD.{N}.log`)
+//! - `.max_rotated_files(5)` to keep at most 5 files rotated by `max_file_size` (`.old{N}`, compressed or not), removing the oldest ones after each rotation, so the single file channel uses a bounded amount of disk (default: keep all)
+//! - `.compress_rotated(true)` to gzip the files rotated by `max_file_size` to `.old{N}.gz`, after the lock of the live file is released, while the live file stays plain text [requires feature `gzip`]
+//! - `.retention_days(7)` to set the number of days to keep the log files (daily file) or rows (SQLite)
+//! - `.max_file_size_str("100MB")` and `.retention_str("2w")` to set the maximum file size (`B`, `KB`, `MB`, `GB`) and the retention (`d`, `w`, `m` of 30 days) from strings with units, e.g. from a configuration file (these return `FtailError::InvalidSize` for a bad value)
+//! - `.session_separator()` to write a `--- new session ---` line to the daily file when the logger starts and the file is not empty
+//! - `.max_level(LevelFilter::Info)` to set the most verbose level logged by any channel
 //! - `.filter_levels(vec![Level::Debug, Level::Error])` only log messages with the specified levels
-//! - `.filter_targets(vec!["foo", "bar"])` only log messages with the specified targets
+//! - `.filter_targets(vec!["foo", "bar"])` only log messages with the specified targets (this appends `Allow` rules, so a `Deny` rule for a sub-target like `foo::db` must be added with `.rules(...)` before it)
+//! - `.filter_targets_regex(vec!["::db$"])?` to only log messages whose target matches any of the regular expressions, e.g. any `db` submodule (returns `FtailError::InvalidRegex` for a bad pattern) [requires feature `regex`]
+//! - `.exclude_targets(vec!["hyper", "h2"])` to drop the messages whose target starts with any of the specified prefixes, e.g. to log everything except noisy dependencies (combined with `.filter_targets`, a message must match an allowed target and no excluded one)
+//! - `.rules(vec![Rule::deny(Some("foo::db"), LevelFilter::Trace), Rule::allow(Some("foo"), LevelFilter::Info)])` to filter messages with ordered rules, the first matching rule decides (messages matching no rule are only logged if there are no `Allow` rules)
+//! - `.up_to(Level::Warn)` after a channel to only log the messages up to the level to it, so the channel logs a window of levels with its level filter, e.g. `.single_file("app.log", true, LevelFilter::Info).up_to(Level::Warn)` for the info messages and warnings, and `.single_file("error.log", true, LevelFilter::Error)` for the errors
+//! - `.filtered(ChannelFilter::new().levels(vec![Level::Warn, Level::Error]).targets(vec!["billing"]))` after a channel to filter it with its own levels, targets and rules instead of the ones of the logger, e.g. to log everything to the console but only the billing warnings and errors to a file
+//! - `.json_schema(ftail::JSON_SCHEMA)` to start the JSON output with a `"_schema":"ftail/1"` field (or any other version, e.g. `"myapp/2"`), so log processors can handle changes of the format
+//! - `.json_promoted_keys(vec!["trace_id", "span_id"])` to write these key-values at the top level of the JSON output, in this order, instead of in the `fields` object, which keeps the other key-values in the order they were logged (a key named after a field of the format, e.g. `level`, stays in `fields`)
+//! - `.source_root("/home/me/proj")` to strip the root of the workspace from the file of the messages, so locations are shorter and the same across machines
+//! - `.location_min_level(Level::Warn)` to only include the file and line of the messages at or above the level, so high-volume info messages stay short (default: all levels)
+//! - `.default_target("app")` to show `app` as the target of the messages logged without an explicit target (whose target is their module path), e.g. `log::info!("...")` but not `log::info!(target: "db", "...")` (the rules still match the original target)
+//! - `.build_info(env!("CARGO_PKG_VERSION"), "4ac8692")` to include the build version and commit in each log message
+//! - `.console_batch_size(64)` to buffer the console channels per thread and write the lines in batches to reduce contention on stdout (lines of different threads may be written out of order; a warning or an error writes the buffer at once, and the buffered lines are written on `flush` and every `flush_interval`, so set one, as the lines still buffered at exit may be lost)
+//! - `.console_split_streams(true)` to write the warnings and errors of the console channels to stderr and the other messages to stdout, e.g. when stdout is parsed in CI (default: everything to stdout)
+//! - `.async_channels(1024)` to run each channel on its own thread with a queue of N records, so a slow channel (e.g. GELF) does not block the others (logging blocks when the queue of a channel is full, unless the `overflow` policy drops records, and `flush` waits for the queues to drain)
+//! - `.non_blocking()` to write the records of the file channels on a single background thread, so logging to a file returns immediately (the queue is unbounded unless `.queue_capacity(N)` is set, `flush` waits for it to drain, and the queued records are written when the logger is dropped)
+//! - `.queue_capacity(10_000)` to bound the queue of the `non_blocking` writer to N records
+//! - `.overflow(OverflowPolicy::DropOldest)` to choose what happens when the queue of the `non_blocking` writer or of an `async_channels` channel is full: block the logging thread (`Block`, the default), drop the record being logged (`DropNewest`) or drop the oldest queued record (`DropOldest`) (the dropped records are reported by a `N records were dropped because the queue was full` warning to their channel, at most every 10 seconds and when it is flushed)
+//! - `.retry_policy(RetryPolicy { max_attempts: 10, ..Default::default() })` to set how the network channels retry a failed send, waiting `base_delay` (100ms) before the first retry and `multiplier` (2) times longer before each next one, up to `max_delay` (10s), minus a random `jitter` (20%) of the delay so many processes do not retry at once (default: 5 attempts)
+//! - `.flush_every(100)` to flush the file channels every N records instead of after every record (warnings and errors are always flushed)
+//! - `.flush_interval(Duration::from_secs(1))` to flush the file channels every interval on a background thread instead of after every record, so logging only writes to the buffers (warnings and errors are always flushed, and the channels are flushed by `flush` and when they are dropped)
+//! - `.dedup_repeats(Duration::from_secs(10))` to collapse the identical records (same level, target and message) repeated within the window after the first one into a single `last message repeated N times` record, e.g. for a retry loop logging the same error (the count is logged before the next different record, before the next identical record after the window, or when the logger is flushed)
+//! - `.max_records(1000)` to stop logging after N records, e.g. to capture a small reproduction (a last warning is logged when the cap is reached)
+//! - `.record_separator(SepStyle::Rule)` to set the separator between records in the formatted console (`Blank`, `None`, `Rule` or `Custom(String)`)
+//! - `.formatted_target(false)` to leave the target out of the formatted console, which shows it after the level by default (`INFO · app::db`)
+//! - `.color_theme(ColorTheme { datetime: ftail::ansi_escape::StyleCode::bright_black, ..Default::default() })` to set the colors of the levels, the datetime and the location in the formatted console, e.g. for dark terminals where the default black is hard to read
+//! - `.field_overflow(Field::Target, Overflow::Truncate(24))` to truncate (with `…`) or wrap (`Overflow::Wrap(80)`, indented to the column of the field) the target or the message of the default format when they are longer than the width (only the console wraps, the file channels keep one record per line)
+//! - `.show_thread(true)` to include the name of the thread that logged the record, or its id when it is unnamed, after the level (`INFO [worker-1] app msg`), as `thread` in JSON and logfmt, e.g. to debug concurrency issues (the records written by the `async_channels` and `non_blocking` workers keep the thread they were logged on)
+//! - `.show_pid(true)` to include the id of the process before the level (`[4242] INFO app msg`), as `pid` in JSON and logfmt, e.g. when several instances write to the same directory
+//! - `.max_message_length(4096)` to cut the messages longer than N characters in every channel and mark them with `…[truncated]`, e.g. when a component logs huge payloads (the datetime, level, target and location are kept)
+//! - `.max_kv_value_length(256)` and `.max_key_values(32)` to cut the key-value values longer than N characters, marked with `…[truncated]`, and to only write the first N key-values of each record, so a single record cannot produce a huge JSON line
+//! - `.force_color(true)` to force the colors of the formatted console on or off, e.g. when piping to a pager that supports colors (by default, the colors are left out when `NO_COLOR` is set or the output is not a terminal)
+//! - `.terminal_title_on_error()` to set the terminal title (e.g. `ERROR · foo::db`) from the console channels when a warning or an error is logged, so a busy terminal multiplexer flags the pane (a warning does not replace an error logged in the last minute, and nothing is written with `NO_COLOR` or when stdout is not a terminal, unless `.force_color(true)`)
+//! - `.backtrace_on_error()` to append the backtrace to error messages in the file channels (full when `RUST_BACKTRACE=full`)
+//! - `.capture_panics()` to log the panics at the error level with the `panic` target (`thread 'main' panicked at src/main.rs:4:5: boom`) when the logger is initialized, before the previous panic hook prints them (a panic while logging the panic is not logged again)
+//! - `.abort_on(Level::Error)` to abort the process after a message at or above the level is logged, to fail fast in tests and CI (the channels are flushed, but destructors do not run, so file footers are not written and any other logger or buffer in the process is lost)
+//! - `.sample_per_message(100)` to only log the 1st, 101st, 201st... occurrences of each distinct message (same level, target and message), e.g. for a chatty loop, with ` (99 similar messages suppressed)` appended to them
+//! - `.message_transform(|record, fields| format!("{} {}", fields.level, fields.message))` to produce the final line of the console and file channels from the record and its rendered fields (`RenderedRecord`, with `formatted` being the line of the channel)
+//! - `.file_formatter(|record, config| format!("{} {}", record.level(), record.args()))` to format the lines of the single and daily file channels with your own closure instead of the default format, keeping their rotation and retention
+//! - `.footer(|stats| format!("{} records", stats.records))` to write a closing line to the file channels when they are dropped
+//! - `.line_ending(LineEnding::Crlf)` to terminate the records of the file channels with `\r\n` instead of `\n`, e.g. for tools on Windows that show the file on a single line
+//!
+//! The default formatting is also available outside of the channels with `ftail::format_record(&record, &config)`.
+//!
+//! With the `kv` feature, the key-values of the records are written too, e.g. `log::info!(user_id = 42; "login")`: after the message as `user_id=42` in the default and logfmt formats, under a `fields` object in JSON (`"fields":{"user_id":42}`), and as a JSON object in the `kvs` column of SQLite. The feature enables the `kv` feature of `log`, so the key-value syntax of the macros is available.
+//!
+//! Durations can be logged in a consistent unit with `ftail::fmt::dur`, e.g. `log::info!("query took {}", ftail::fmt::dur(elapsed))` logs `query took 12.345ms`. The precision can be set in the format string (`{:.1}`) and the unit with `.unit(ftail::fmt::Unit::Secs)` (`Nanos`, `Micros`, `Millis` or `Secs`).
+//!
+//! The formatted console output, including the escape codes of the colors, is available with `ftail::format_record_readable(&record, &config)`, e.g. to assert the colors in tests.
+//!
+//! Messages logged with the `ftail::raw` target (`ftail::RAW_TARGET`) are written verbatim without the datetime, level and target, e.g. `log::info!(target: ftail::RAW_TARGET, "{}", line)`.
+//!
+//! `.init()` returns a `FtailHandle` that can be used to change the logger after it has been initialized:
+//!
+//! - `.add_channel_dynamic(constructor, level)` to add a channel, which uses the configuration of the logger
+//! - `.current_path("app")` and `.current_size("app")` to get the path and size of the file the single, daily, hourly or file handle channel named with `.named("app")` is writing to, e.g. today's file of a daily file channel (the size includes the records that are not flushed yet)
+//! - `.rotate_now("app")` to close the current file of the single, daily or hourly file channel named with `.named("app")` and continue in a fresh one, e.g. before collecting a snapshot of the logs (a single file is renamed to `<path>.old`, a daily file continues in the next file of the day)
+//! - `.flush_channel("audit")` to flush only the channels named with `.named("audit")`, e.g. before a checkpoint (returns false when there is no channel with the name)
+//! - `.log_record(&record)` to log a record built by hand, e.g. to bridge another log source, through the same filters and channels as the `log` macros
+//! - `.close()` to flush and drop the channels, e.g. at shutdown, returning `FtailError::WriteErrors` with the number of failed writes and the first error of the session (a failed write never panics: the record is dropped, and the first error is also printed to stderr)
+//! - `.reconfigure(Ftail::new().console(LevelFilter::Info))` to replace the channels and configuration of the logger, e.g. to reload the configuration (the old channels are flushed and dropped)
+//! - `.set_level(LevelFilter::Debug)` to change the most verbose level logged by every channel at runtime, replacing their own levels and `.max_level`, e.g. to log the debug records of a running service without a restart (the rules and level filters still apply)
+//! - `.set_target_filter(vec!["app::db"])` to only log the records with the targets, in addition to the other filters, so the `Deny` rules still apply (an empty list logs all the targets allowed by the filters again)
+//! - `.set_channel_enabled("stdout", false)` to disable or enable the channels named with `.named("stdout")` after adding them, e.g. `.console(LevelFilter::Info).named("stdout")`
+//!
+//! The channels and configuration are stored behind a `RwLock`, so logging takes a (shared) read lock on them.
 //!
 //! ## Channels
 //!
+//! Each channel takes a minimum `level`. Use `LevelFilter::Trace` to log all messages and `LevelFilter::Off` to log nothing.
+//!
 //! ### Console
 //!
 //! Logs to the standard output without any formatting.
@@ -84,27 +191,65 @@
 //! ```
 //!
 //! ```sh
-//! 2024-09-13 17:35:37 · TRACE
+//! 2024-09-13 17:35:37 · TRACE · formatted_console
 //! This is a trace message
 //! examples\formatted_console\src/main.rs:9
 //!
-//! 2024-09-13 17:35:37 · DEBUG
+//! 2024-09-13 17:35:37 · DEBUG · formatted_console
 //! This is a debug message
 //! examples\formatted_console\src/main.rs:11
 //!
-//! 2024-09-13 17:35:37 · INFO
+//! 2024-09-13 17:35:37 · INFO · foo
 //! bar
 //! examples\formatted_console\src/main.rs:13
 //!
-//! 2024-09-13 17:35:37 · WARN
+//! 2024-09-13 17:35:37 · WARN · formatted_console
 //! This is a warning message
 //! examples\formatted_console\src/main.rs:15
 //!
-//! 2024-09-13 17:35:37 · ERROR
+//! 2024-09-13 17:35:37 · ERROR · formatted_console
 //! This is an error message
 //! examples\formatted_console\src/main.rs:17
 //! ```
 //!
+//! ### Console JSON
+//!
+//! Logs to the standard output as newline-delimited JSON, one object per message.
+//!
+//! The `console_json` channel takes the following parameters:
+//!
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .console_json(LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ```sh
+//! {"timestamp":"2024-09-13 17:35:18","level":"INFO","target":"foo","message":"bar","file":"src/main.rs","line":13}
+//! ```
+//!
+//! Use the `console_json_pretty` channel, with the same parameters, to log indented multi-line JSON instead, e.g. to read the fields during development. It is not newline-delimited, so it is not suitable for machine ingestion. Requires the `pretty-json` feature.
+//!
+//! ### Console logfmt
+//!
+//! Logs to the standard output as logfmt, one line per message with the `ts`, `level`, `target` and `msg` keys, and the `caller` key when the file and line are known. Values with spaces, `=`, quotes or control characters are quoted, with the quotes and control characters escaped.
+//!
+//! The `logfmt_console` channel takes the following parameters:
+//!
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .logfmt_console(LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ```sh
+//! ts="2024-09-13 17:35:18" level=info target=foo msg="Hello, world!" caller=src/main.rs:13
+//! ```
+//!
 //! ### Single file
 //!
 //! Logs to the single log file `logs/demo.log`.
@@ -121,9 +266,87 @@
 //!     .init()?;
 //! ```
 //!
+//! Use the `json_file` channel, with the same parameters, to log newline-delimited JSON instead, one object per record with the `timestamp`, `level`, `target` and `message` fields, and the `file` and `line` fields when they are known. The timestamp follows `.datetime_format`, e.g. `.datetime_format("%+")` for RFC 3339.
+//!
+//! Use the `logfmt_file` channel, with the same parameters, to log logfmt instead, in the format of the `logfmt_console` channel.
+//!
+//! ### Dual file
+//!
+//! Logs formatted messages to two files, with the colors in one (e.g. for `less -R`) and without them in the other (e.g. for `grep`). Each record is formatted once, and the colors are stripped for the plain file. The files are appended to.
+//!
+//! The `dual_file` channel takes the following parameters:
+//!
+//! - `colored_path`: the path to the log file with colors
+//! - `plain_path`: the path to the log file without colors
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .dual_file("logs/color.log", "logs/plain.log", LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ### File handle
+//!
+//! Logs to a file that is already open, e.g. a file locked elsewhere. The file is not reopened or rotated.
+//!
+//! The `file_handle` channel takes the following parameters:
+//!
+//! - `file`: the open `std::fs::File`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! let file = std::fs::OpenOptions::new().append(true).open("logs/demo.log")?;
+//!
+//! Ftail::new()
+//!     .file_handle(file, LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ### Writer
+//!
+//! Logs formatted messages to any writer, e.g. a `Vec<u8>`, a pipe or a struct of your own implementing `std::io::Write`, without implementing the `Log` trait.
+//!
+//! The `writer` channel takes the following parameters:
+//!
+//! - `writer`: the writer, `std::io::Write + Send`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! let (_reader, pipe) = std::io::pipe()?;
+//!
+//! Ftail::new()
+//!     .writer(pipe, LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ### Shared file
+//!
+//! Logs to a single log file through several views, each with its own level and format.
+//! The views share one file handle, so their lines are never interleaved.
+//!
+//! The `shared_file` method takes the following parameters:
+//!
+//! - `path`: the path to the log file
+//! - `append`: whether to append to the log file or overwrite it
+//!
+//! The `view` channel takes the following parameters and writes to the last shared file:
+//!
+//! - `level`: the minumum log level to log
+//! - `formatter`: a closure formatting the record, e.g. `ftail::format_record`
+//!
+//! ```rust
+//! Ftail::new()
+//!     .shared_file("logs/demo.log", true)
+//!     .view(LevelFilter::Info, ftail::format_record)
+//!     .view(LevelFilter::Error, |record, _config| format!("ALERT {}", record.args()))
+//!     .init()?;
+//! ```
+//!
 //! ### Daily file
 //!
 //! Logs to a daily log file in the `logs` directory. The log files have the following format: `YYYY-MM-DD.log`.
+//! When the file exceeds the `max_file_size`, the logger continues in the next file of the day: `YYYY-MM-DD.1.log`, `YYYY-MM-DD.2.log`, ...
 //!
 //! The `daily_file` channel takes the following parameters:
 //!
@@ -136,6 +359,228 @@
 //!     .init()?;
 //! ```
 //!
+//! Use the `daily_json_file` channel, with the same parameters, to log newline-delimited JSON instead. It creates the directory when it does not exist.
+//!
+//! Use the `daily_file_pattern` channel to name the files with a [chrono pattern](https://docs.rs/chrono/latest/chrono/format/strftime/index.html) instead, e.g. `app-%Y-%m-%d.jsonl`; the rest of the pattern is kept as is. A new file is started whenever the formatted name changes, so `%Y_%m_%d_%H.log` starts a new file every hour. The retention recognizes the files by the same pattern.
+//!
+//! ```rust
+//! Ftail::new()
+//!     .daily_file_pattern("logs", "app-%Y-%m-%d.log", LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ### Hourly file
+//!
+//! Logs to a new file every hour in the `logs` directory. The log files have the following format: `YYYY-MM-DD-HH.log`.
+//! When the file exceeds the `max_file_size`, the logger continues in the next file of the hour: `YYYY-MM-DD-HH.1.log`, ... With `.retention_days(n)`, the files older than `n` days are removed.
+//!
+//! The `hourly_file` channel takes the following parameters:
+//!
+//! - `dir`: the directory to store the log files
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .hourly_file("logs", LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! For another separator, e.g. `YYYY_MM_DD_HH.log` or `YYYYMMDDHH.log`, use the `daily_file_pattern` channel with the hour in the pattern. The same pattern names the rotated files and the files removed by the retention.
+//!
+//! ```rust
+//! Ftail::new()
+//!     .daily_file_pattern("logs", "%Y_%m_%d_%H.log", LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ### Striped files
+//!
+//! Logs across multiple files in the `logs` directory. The log files have the following format: `part{N}.log`.
+//! When the current file exceeds the maximum size, the next file is truncated and written to.
+//!
+//! The `striped_files` channel takes the following parameters:
+//!
+//! - `dir`: the directory to store the log files
+//! - `n`: the number of files to write to
+//! - `max_size_in_mb`: the maximum size of each file in MB
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .striped_files("logs", 4, 100, LevelFilter::Trace)
+//!     .init()?;
+//! ```
+//!
+//! ### Elasticsearch
+//!
+//! Indexes the records in Elasticsearch with the `_bulk` API, as documents with the `@timestamp`, `level`, `target`, `message`, `file` and `line` fields. The records are sent by a background thread in batches of up to 500 records or every second. When the node is unavailable, the batch is retried following `.retry_policy`, and the documents rejected with `429` or `5xx` are retried as well. Documents that are given up on are counted as failed writes (see `.close()`). Requires the `elasticsearch` feature.
+//!
+//! The URL is a plain HTTP URL, `http://[user:password@]host[:port][/prefix]` (the port defaults to `9200`). For HTTPS, use a TLS-terminating proxy.
+//!
+//! The `elasticsearch` channel takes the following parameters:
+//!
+//! - `url`: the URL of the Elasticsearch node
+//! - `index`: the index (or data stream) to index the records in
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .elasticsearch("http://localhost:9200", "logs", LevelFilter::Info)
+//!     .init()?;
+//! ```
+//!
+//! ### GELF
+//!
+//! Sends GELF (Graylog) messages over UDP. Large messages are chunked. Requires the `gelf` feature.
+//!
+//! The `gelf` channel takes the following parameters:
+//!
+//! - `addr`: the address of the Graylog UDP input
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .gelf("graylog:12201", LevelFilter::Info)
+//!     .init()?;
+//! ```
+//!
+//! ### SQLite
+//!
+//! Inserts the records into a SQLite database, in a `logs` table with the `ts`, `level`, `target`, `file`, `line`, `message` and `kvs` columns. The table is created if missing, and the rows are written in batches by a background thread. With `.retention_days(n)`, rows older than `n` days are deleted. Requires the `sqlite` feature.
+//!
+//! The `sqlite` channel takes the following parameters:
+//!
+//! - `path`: the path to the database file
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .sqlite("logs.db", LevelFilter::Info)
+//!     .retention_days(30)
+//!     .init()?;
+//! ```
+//!
+//! ### Syslog
+//!
+//! Sends RFC 5424 syslog messages, e.g. to rsyslog, with the hostname, the name of the executable as the app name, the process id, and the target as the message id. The timestamp is in RFC 3339, as required by RFC 5424. Over UDP each message is a datagram; over TCP the messages are framed with octet counting, and the connection is opened again on the next message after an error, waiting between failed connects following `.retry_policy` (messages logged while disconnected are counted as failed writes). Requires the `syslog` feature.
+//!
+//! The `syslog` channel takes the following parameters:
+//!
+//! - `addr`: the address of the syslog server, `udp://host:port` or `tcp://host:port` (UDP when there is no scheme)
+//! - `facility`: the syslog facility, e.g. `16` for local0
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .syslog("udp://localhost:514", 16, LevelFilter::Info)
+//!     .init()?;
+//! ```
+//!
+//! ### TCP
+//!
+//! Streams formatted messages to a TCP server, e.g. a log aggregator, one line per message in the format of the file channels. The connection is opened on the first message, and again on the next message after an error, waiting between failed connects following `.retry_policy` (messages logged while disconnected are counted as failed writes). The messages are buffered, and flushed as configured with `.flush_every`. Requires the `tcp` feature.
+//!
+//! The `tcp` channel takes the following parameters:
+//!
+//! - `addr`: the address of the server, `host:port`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .tcp("aggregator:5170", LevelFilter::Info)
+//!     .init()?;
+//! ```
+//!
+//! ### Debug output
+//!
+//! Sends messages to the attached debugger with `OutputDebugStringW`, e.g. for Windows GUI applications without a console. Requires the `windows` feature and is only available on Windows.
+//!
+//! The `debug_output` channel takes the following parameters:
+//!
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .debug_output(LevelFilter::Debug)
+//!     .init()?;
+//! ```
+//!
+//! ### Mpsc
+//!
+//! Sends the rendered records to an mpsc channel, e.g. to display them in the event loop of your application.
+//! The sender can be a `Sender` or a `SyncSender`. Records are dropped when a `SyncSender` is full.
+//!
+//! The `mpsc` channel takes the following parameters:
+//!
+//! - `sender`: the sender of the channel that receives the `RenderedRecord`s
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! let (sender, receiver) = std::sync::mpsc::sync_channel(100);
+//!
+//! Ftail::new()
+//!     .mpsc(sender, LevelFilter::Info)
+//!     .init()?;
+//! ```
+//!
+//! ### Broadcast
+//!
+//! Sends the rendered records to every subscriber of a `Broadcast`, e.g. to stream them to the websocket connections of a live log viewer. Subscribers only receive the records logged after they subscribed. A subscriber whose queue is full misses records instead of blocking the logger.
+//!
+//! The `broadcast` channel takes the following parameters:
+//!
+//! - `broadcast`: the `Broadcast` (`ftail::channels::broadcast::Broadcast`) to send the `RenderedRecord`s to
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! let broadcast = Broadcast::new(100);
+//!
+//! Ftail::new()
+//!     .broadcast(&broadcast, LevelFilter::Info)
+//!     .init()?;
+//!
+//! // e.g. for each websocket connection
+//! let receiver = broadcast.subscribe();
+//! ```
+//!
+//! ### Memory
+//!
+//! Keeps the last formatted lines in a `MemoryBuffer`, in the format of the file channels, e.g. to show the recent logs in an admin panel. When the buffer is full, the oldest line is dropped. The buffer is cheap to clone, and its clones share the same lines.
+//!
+//! The `memory` channel takes the following parameters:
+//!
+//! - `buffer`: the `MemoryBuffer` (`ftail::channels::memory::MemoryBuffer`) to keep the lines in
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! let buffer = MemoryBuffer::new(500);
+//!
+//! Ftail::new()
+//!     .memory(&buffer, LevelFilter::Info)
+//!     .init()?;
+//!
+//! // e.g. in the handler of the admin panel, from the oldest to the most recent line
+//! let lines = buffer.snapshot();
+//! ```
+//!
+//! ### Callback
+//!
+//! Calls a closure for each record, with the configuration of the channel, e.g. for an ad-hoc integration without implementing the `Log` trait. The closure is called on the thread that logs the record. The records the closure logs itself are dropped, like the ones logged by any channel, since the logger is already logging on that thread.
+//!
+//! The `callback` channel takes the following parameters:
+//!
+//! - `callback`: the closure, `Fn(&log::Record, &Config) + Send + Sync`
+//! - `level`: the minumum log level to log
+//!
+//! ```rust
+//! Ftail::new()
+//!     .callback(
+//!         |record, config| report(ftail::format_record(record, config)),
+//!         LevelFilter::Error,
+//!     )
+//!     .init()?;
+//! ```
+//!
 //! ### Custom channel
 //!
 //! Create your own log channel.
@@ -156,10 +601,6 @@
 //!
 //! impl Log for CustomLogger {
 //!     fn enabled(&self, metadata: &log::Metadata) -> bool {
-//!         if self.config.level_filter == LevelFilter::Off {
-//!             return true;
-//!         }
-//!
 //!         metadata.level() <= self.config.level_filter
 //!     }
 //!
@@ -187,23 +628,69 @@
 //! ```
 
 use channels::{
-    console::ConsoleLogger, daily_file::DailyFileLogger, formatted_console::FormattedConsoleLogger,
+    broadcast::{Broadcast, BroadcastLogger},
+    callback::CallbackLogger,
+    console::ConsoleLogger,
+    console_json::JsonConsoleLogger,
+    console_logfmt::LogfmtConsoleLogger,
+    daily_file::DailyFileLogger,
+    dual_file::DualFileLogger,
+    formatted_console::FormattedConsoleLogger,
+    hourly_file::HourlyFileLogger,
+    memory::{MemoryBuffer, MemoryLogger},
+    mpsc::{MpscLogger, RecordSender},
+    non_blocking::{BackgroundWriter, NonBlockingLogger},
+    queued::QueuedLogger,
+    shared_file::{SharedFile, SharedFileLogger},
     single_file::SingleFileLogger,
+    striped_files::StripedFilesLogger,
+    writer::WriterLogger,
 };
+use dedup::{Repeated, Repeats};
 use error::FtailError;
+use formatters::{default::DefaultFormatter, readable::ReadableFormatter, Formatter};
+use helpers::CurrentFile;
 use log::{Level, LevelFilter, Log};
+use sample::Samples;
+use std::{
+    cell::{Cell, RefCell},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
 
 #[cfg(feature = "timezone")]
 pub use chrono_tz::Tz;
+pub use formatters::{
+    default::{Field, Overflow, RAW_TARGET},
+    json::JSON_SCHEMA,
+    readable::{ColorTheme, SepStyle},
+};
+pub use handle::FtailHandle;
+pub use helpers::LineEnding;
+pub use queue::OverflowPolicy;
+pub use retry::RetryPolicy;
+pub use rules::{ChannelFilter, Rule, RuleAction};
 
 /// Module containing the ANSI escape codes.
 pub mod ansi_escape;
 /// Module containing the channels.
 pub mod channels;
+mod dedup;
 /// Module containing the error type.
 pub mod error;
+pub mod fmt;
 mod formatters;
+mod handle;
 mod helpers;
+mod kv;
+mod panic;
+mod queue;
+mod retry;
+mod rules;
+mod sample;
 #[cfg(test)]
 mod tests;
 mod writer;
@@ -211,8 +698,33 @@ mod writer;
 /// The main struct for configuring the logger.
 pub struct Ftail {
     channels: Vec<LogChannel>,
-    initialized_channels: Vec<InitializedLogChannel>,
+    state: Arc<RwLock<LoggerState>>,
+    shared_file: Option<SharedFile>,
     config: Config,
+    /// The number of rules at the start of `config.rules` read from `FTAIL_TARGETS`, which
+    /// `filter_targets` replaces.
+    env_rules: usize,
+}
+
+/// The channels and configuration of an initialized logger, shared with its handles.
+#[derive(Default)]
+pub(crate) struct LoggerState {
+    pub(crate) channels: Vec<InitializedLogChannel>,
+    /// The background writer of the file channels with `non_blocking`, shared with the channels
+    /// added by `FtailHandle::add_channel_dynamic`.
+    pub(crate) writer: Option<BackgroundWriter>,
+    pub(crate) records: AtomicU64,
+    pub(crate) samples: Samples,
+    pub(crate) repeats: Repeats,
+    pub(crate) write_errors: WriteErrors,
+    /// Dropped with the state when it is replaced, which stops its flush timer.
+    pub(crate) flush_timer: Arc<()>,
+    /// The level set with `FtailHandle::set_level`, `0` when unset, otherwise the `LevelFilter`
+    /// plus one. It replaces the levels of the channels and `max_level`.
+    pub(crate) level: AtomicUsize,
+    /// The targets set with `FtailHandle::set_target_filter`, checked with the other filters.
+    pub(crate) target_filter: Option<Vec<String>>,
+    pub(crate) config: Config,
 }
 
 unsafe impl Send for Ftail {}
@@ -221,10 +733,21 @@ unsafe impl Sync for Ftail {}
 pub(crate) struct LogChannel {
     constructor: Box<dyn Fn(Config) -> Box<dyn Log + Send + Sync>>,
     level: log::LevelFilter,
+    name: Option<String>,
+    filter: Option<ChannelFilter>,
+    up_to: Option<Level>,
+    datetime_format: Option<String>,
+    current_file: Option<Arc<CurrentFile>>,
 }
 
 pub(crate) struct InitializedLogChannel {
     channel: Box<dyn Log + Send + Sync>,
+    level: LevelFilter,
+    name: Option<String>,
+    enabled: AtomicBool,
+    filter: Option<ChannelFilter>,
+    up_to: Option<Level>,
+    current_file: Option<Arc<CurrentFile>>,
 }
 
 /// The configuration struct for the logger. Required for custom channels.
@@ -232,110 +755,1188 @@ pub(crate) struct InitializedLogChannel {
 pub struct Config {
     pub level_filter: LevelFilter,
     pub datetime_format: String,
+    pub utc_suffix: bool,
     #[cfg(feature = "timezone")]
     pub timezone: chrono_tz::Tz,
     pub max_file_size: Option<u64>,
+    pub max_rotated_files: Option<usize>,
+    #[cfg(feature = "gzip")]
+    pub compress_rotated: bool,
     pub retention_days: Option<u64>,
+    pub session_separator: bool,
+    pub max_level: Option<LevelFilter>,
     pub levels: Option<Vec<Level>>,
+    #[deprecated(note = "use `rules`, to which `Ftail::filter_targets` appends `Allow` rules")]
     pub targets: Option<Vec<String>>,
+    pub rules: Vec<Rule>,
+    pub excluded_targets: Option<Vec<String>>,
+    #[cfg(feature = "regex")]
+    pub target_regexes: Option<Vec<regex::Regex>>,
+    pub footer: Option<Footer>,
+    pub flush_every: Option<u64>,
+    pub flush_interval: Option<Duration>,
+    pub max_records: Option<u64>,
+    pub version: Option<String>,
+    pub commit: Option<String>,
+    pub console_batch_size: Option<usize>,
+    pub console_split_streams: bool,
+    pub async_capacity: Option<usize>,
+    pub non_blocking: bool,
+    pub queue_capacity: Option<usize>,
+    pub overflow: OverflowPolicy,
+    pub record_separator: SepStyle,
+    pub line_ending: LineEnding,
+    pub color_theme: ColorTheme,
+    pub force_color: Option<bool>,
+    pub formatted_target: bool,
+    pub target_overflow: Option<Overflow>,
+    pub message_overflow: Option<Overflow>,
+    pub max_message_length: Option<usize>,
+    pub max_kv_value_length: Option<usize>,
+    pub max_key_values: Option<usize>,
+    pub show_thread: bool,
+    pub show_pid: bool,
+    pub terminal_title_on_error: bool,
+    pub template: Option<String>,
+    pub json_schema: Option<String>,
+    pub json_promoted_keys: Option<Vec<String>>,
+    pub source_root: Option<String>,
+    pub location_min_level: Option<Level>,
+    pub default_target: Option<String>,
+    pub syslog_facility: u8,
+    pub message_transform: Option<MessageTransform>,
+    pub file_formatter: Option<RecordFormat>,
+    pub backtrace_on_error: bool,
+    pub abort_on: Option<Level>,
+    pub sample_per_message: Option<u64>,
+    pub capture_panics: bool,
+    pub dedup_window: Option<Duration>,
+    pub retry_policy: RetryPolicy,
+    pub clock: Clock,
 }
 
-impl Ftail {
-    /// Create a new instance of `Ftail`.
-    pub fn new() -> Self {
-        Self {
-            channels: Vec::new(),
-            initialized_channels: Vec::new(),
-            config: Config::new(),
-        }
+/// A record rendered by the logger, sent to channels consumed by the application.
+#[derive(Debug, Clone)]
+pub struct RenderedRecord {
+    pub level: Level,
+    pub target: String,
+    pub datetime: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// The record formatted by the default formatter.
+    pub formatted: String,
+}
+
+impl RenderedRecord {
+    pub(crate) fn new(record: &log::Record, config: &Config) -> Self {
+        Self::with_formatted(record, config, format_record(record, config))
     }
 
-    #[cfg(feature = "timezone")]
-    /// Set the timezone for the logger.
-    pub fn timezone(mut self, timezone: chrono_tz::Tz) -> Self {
-        self.config.timezone = timezone;
+    pub(crate) fn with_formatted(record: &log::Record, config: &Config, formatted: String) -> Self {
+        let writer = writer::LogWriter::new(record, config);
 
-        self
+        RenderedRecord {
+            level: record.level(),
+            target: writer.get_target(),
+            datetime: writer.get_datetime(),
+            message: writer.get_args(),
+            file: writer.get_file(),
+            line: writer.get_line(),
+            formatted,
+        }
     }
+}
 
-    /// Set the datetime format for the logger.
-    pub fn datetime_format(mut self, datetime_format: &str) -> Self {
+/// The closure that renders the footer of the file channels.
+pub type Footer = Arc<dyn Fn(&ChannelStats) -> String + Send + Sync>;
+
+/// The clock used by the file channels to pick the current file and apply retention.
+pub type Clock = Arc<dyn Fn() -> chrono::DateTime<chrono::Local> + Send + Sync>;
+
+/// The closure that produces the final line from the record and its rendered fields.
+pub type MessageTransform = Arc<dyn Fn(&log::Record, &RenderedRecord) -> String + Send + Sync>;
+
+/// The closure that formats a record, used by the views of a shared file and `file_formatter`.
+pub type RecordFormat = Arc<dyn Fn(&log::Record, &Config) -> String + Send + Sync>;
+
+/// The statistics of a channel, passed to the footer of the file channels.
+#[derive(Debug, Clone)]
+pub struct ChannelStats {
+    pub records: u64,
+    pub warnings: u64,
+    pub errors: u64,
+    pub duration: Duration,
+}
+
+/// The failed writes of the channels, kept by the state of the logger to report them when it is
+/// closed. The channels constructed by `LogChannel::init` report to the ones of the logger.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WriteErrors {
+    errors: Arc<Mutex<(u64, Option<std::io::Error>)>>,
+}
+
+thread_local! {
+    /// The failed writes of the logger whose channels are constructed on this thread.
+    static CONSTRUCTING: RefCell<Option<WriteErrors>> = const { RefCell::new(None) };
+}
+
+impl WriteErrors {
+    /// Count a failed write. The first one is reported on stderr, as `log` cannot return it to
+    /// the caller, and the record is dropped.
+    pub(crate) fn push(&self, error: std::io::Error) {
+        let mut errors = self
+            .errors
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if errors.1.is_none() {
+            eprintln!("ftail: a record could not be written: {}", error);
+        }
+
+        errors.0 += 1;
+        errors.1.get_or_insert(error);
+    }
+
+    /// Take the number of failed writes and the first error.
+    pub(crate) fn take(&self) -> Option<(u64, std::io::Error)> {
+        let mut errors = self.errors.lock().unwrap();
+        let count = std::mem::take(&mut errors.0);
+
+        errors.1.take().map(|error| (count, error))
+    }
+
+    /// Run `f`, usually the constructor of a channel, so the channels it constructs report their
+    /// failed writes here.
+    pub(crate) fn collect<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = CONSTRUCTING.replace(Some(self.clone()));
+        let result = f();
+        CONSTRUCTING.set(previous);
+
+        result
+    }
+
+    /// The failed writes of the logger constructing the channel, or new ones for a channel
+    /// constructed on its own.
+    pub(crate) fn current() -> Self {
+        CONSTRUCTING.with_borrow(|errors| errors.clone().unwrap_or_default())
+    }
+}
+
+impl Ftail {
+    /// Create a new instance of `Ftail`.
+    pub fn new() -> Self {
+        Self {
+            channels: Vec::new(),
+            state: Arc::new(RwLock::new(LoggerState::default())),
+            shared_file: None,
+            config: Config::new(),
+            env_rules: 0,
+        }
+    }
+
+    /// Create a new instance of `Ftail` configured from the environment: `FTAIL_LEVEL` (e.g.
+    /// `info`, see `max_level`), `FTAIL_LEVELS` and `FTAIL_TARGETS` (comma-separated, see
+    /// `filter_levels` and `filter_targets`) and `FTAIL_DATETIME_FORMAT`. The builder methods
+    /// called afterwards take precedence. Returns `FtailError::EnvParseError` for a bad value.
+    pub fn from_env() -> Result<Self, FtailError> {
+        let mut ftail = Self::new();
+
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let invalid = |name: &str, value: &str| {
+            FtailError::EnvParseError(name.to_string(), value.to_string())
+        };
+
+        if let Some(level) = var("FTAIL_LEVEL") {
+            let level = helpers::parse_level_filter(&level)
+                .ok_or_else(|| invalid("FTAIL_LEVEL", &level))?;
+
+            ftail = ftail.max_level(level);
+        }
+
+        if let Some(levels) = var("FTAIL_LEVELS") {
+            let levels = levels
+                .split(',')
+                .map(|level| {
+                    helpers::parse_level_filter(level)
+                        .and_then(|level| level.to_level())
+                        .ok_or_else(|| invalid("FTAIL_LEVELS", level))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            ftail = ftail.filter_levels(levels);
+        }
+
+        if let Some(targets) = var("FTAIL_TARGETS") {
+            ftail = ftail.filter_targets(targets.split(',').map(str::trim).collect());
+            ftail.env_rules = ftail.config.rules.len();
+        }
+
+        if let Some(format) = var("FTAIL_DATETIME_FORMAT") {
+            if chrono::format::StrftimeItems::new(&format)
+                .any(|item| item == chrono::format::Item::Error)
+            {
+                return Err(invalid("FTAIL_DATETIME_FORMAT", &format));
+            }
+
+            ftail = ftail.datetime_format(&format);
+        }
+
+        Ok(ftail)
+    }
+
+    /// Create a logger for development: formatted and colored console output from the debug level,
+    /// with the location of each message.
+    pub fn dev() -> Self {
+        Self::new().formatted_console(LevelFilter::Debug)
+    }
+
+    /// Create a logger for production: JSON in daily files in the `logs` directory from the info level,
+    /// kept for 30 days. The directory is created when it does not exist.
+    pub fn prod() -> Self {
+        Self::new()
+            .time_iso()
+            .retention_days(30)
+            .daily_json_file("logs", LevelFilter::Info)
+    }
+
+    #[cfg(feature = "timezone")]
+    /// Set the timezone for the logger.
+    pub fn timezone(mut self, timezone: chrono_tz::Tz) -> Self {
+        self.config.timezone = timezone;
+
+        self
+    }
+
+    /// Set the datetime format for the logger.
+    pub fn datetime_format(mut self, datetime_format: &str) -> Self {
         self.config.datetime_format = datetime_format.to_string();
 
-        self
+        self
+    }
+
+    /// Set the datetime format to a 12-hour clock, e.g. `2024-09-13 05:35:18 PM`.
+    pub fn time_12h(self) -> Self {
+        self.datetime_format("%Y-%m-%d %I:%M:%S %p")
+    }
+
+    /// Set the datetime format to a 24-hour clock, e.g. `2024-09-13 17:35:18`.
+    pub fn time_24h(self) -> Self {
+        self.datetime_format("%Y-%m-%d %H:%M:%S")
+    }
+
+    /// Set the datetime format to ISO 8601, e.g. `2024-09-13T17:35:18.123+02:00`.
+    pub fn time_iso(self) -> Self {
+        self.datetime_format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+    }
+
+    /// Append `Z` to the datetime when it is in UTC and the format does not include the offset.
+    pub fn utc_suffix(mut self) -> Self {
+        self.config.utc_suffix = true;
+
+        self
+    }
+
+    /// Set the template of the default format, e.g. `{datetime} {level} {target} {message}`.
+    pub fn template(mut self, template: &str) -> Self {
+        self.config.template = Some(template.to_string());
+
+        self
+    }
+
+    /// Set the syslog facility used by the `{priority}` token, `1` (user) by default.
+    pub fn syslog_facility(mut self, facility: u8) -> Self {
+        self.config.syslog_facility = facility.min(23);
+
+        self
+    }
+
+    /// Include a `_schema` field with the given version in the JSON output, e.g. `ftail::JSON_SCHEMA`,
+    /// so log processors can handle changes of the format.
+    pub fn json_schema(mut self, schema: &str) -> Self {
+        self.config.json_schema = Some(schema.to_string());
+
+        self
+    }
+
+    /// Write these key-values at the top level of the JSON output, in this order, e.g. `trace_id`
+    /// for the schemas that expect it there. The other key-values stay in the `fields` object, in
+    /// the order they were logged, as do the keys named after a field of the format, e.g. `level`.
+    pub fn json_promoted_keys(mut self, keys: Vec<&str>) -> Self {
+        self.config.json_promoted_keys = Some(keys.into_iter().map(str::to_string).collect());
+
+        self
+    }
+
+    /// Strip the root of the workspace from the file of the messages, e.g. `/home/me/proj`.
+    pub fn source_root(mut self, source_root: &str) -> Self {
+        self.config.source_root = Some(source_root.to_string());
+
+        self
+    }
+
+    /// Only include the file and line of the messages at or above the level, e.g. `Level::Warn`.
+    pub fn location_min_level(mut self, level: Level) -> Self {
+        self.config.location_min_level = Some(level);
+
+        self
+    }
+
+    /// Show `target` instead of the target of the records logged without an explicit target, whose
+    /// target is their module path.
+    pub fn default_target(mut self, target: &str) -> Self {
+        self.config.default_target = Some(target.to_string());
+
+        self
+    }
+
+    /// Set the build version and commit to include in each log message.
+    pub fn build_info(mut self, version: &str, commit: &str) -> Self {
+        self.config.version = Some(version.to_string());
+        self.config.commit = Some(commit.to_string()).filter(|commit| !commit.is_empty());
+
+        self
+    }
+
+    /// Set how the network channels retry a failed send, with exponential backoff and jitter.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.config.retry_policy = retry_policy;
+
+        self
+    }
+
+    #[cfg(feature = "gzip")]
+    /// Gzip the files rotated by `max_file_size` to `.old{N}.gz`. The live file stays plain text.
+    pub fn compress_rotated(mut self, compress: bool) -> Self {
+        self.config.compress_rotated = compress;
+
+        self
+    }
+
+    /// Set the maximum file size for the logger.
+    pub fn max_file_size(mut self, max_file_size_in_mb: u64) -> Self {
+        self.config.max_file_size = Some(max_file_size_in_mb * 1024 * 1024);
+
+        self
+    }
+
+    /// Keep at most `n` files rotated by `max_file_size` (`.old{N}`), removing the oldest ones.
+    /// The default is to keep all of them.
+    pub fn max_rotated_files(mut self, n: usize) -> Self {
+        self.config.max_rotated_files = Some(n);
+
+        self
+    }
+
+    /// Set the maximum file size from a size with a unit: `B`, `KB`, `MB` or `GB`, e.g. `"100MB"`.
+    pub fn max_file_size_str(mut self, max_file_size: &str) -> Result<Self, FtailError> {
+        self.config.max_file_size = Some(helpers::parse_size(max_file_size)?);
+
+        Ok(self)
+    }
+
+    /// Set the retention days for the logger (daily file and SQLite loggers only).
+    pub fn retention_days(mut self, retention_days: u64) -> Self {
+        self.config.retention_days = Some(retention_days);
+
+        self
+    }
+
+    /// Set the retention from a duration with a unit: `d`, `w` or `m` (30 days), e.g. `"2w"`.
+    pub fn retention_str(mut self, retention: &str) -> Result<Self, FtailError> {
+        self.config.retention_days = Some(helpers::parse_retention_days(retention)?);
+
+        Ok(self)
+    }
+
+    /// Write a separator line to the daily file when the logger starts and the file is not empty.
+    pub fn session_separator(mut self) -> Self {
+        self.config.session_separator = true;
+
+        self
+    }
+
+    /// Set the most verbose level logged by any channel, e.g. to lower the levels of all the
+    /// channels at once.
+    pub fn max_level(mut self, level: LevelFilter) -> Self {
+        self.config.max_level = Some(level);
+
+        self
+    }
+
+    /// Only log messages with the specified levels. The default is to log all levels.
+    pub fn filter_levels(mut self, levels: Vec<Level>) -> Self {
+        self.config.levels = Some(levels);
+
+        self
+    }
+
+    /// Only log messages with the specified targets. The default is to log all targets.
+    /// This appends an `Allow` rule for each target. The rules are evaluated in order and the
+    /// first matching rule decides, so a `Deny` rule for a sub-target, e.g. `foo::db`, must be
+    /// added with `rules` before `filter_targets(vec!["foo"])`. The targets read from
+    /// `FTAIL_TARGETS` by `from_env` are replaced.
+    pub fn filter_targets(mut self, targets: Vec<&str>) -> Self {
+        self.config
+            .rules
+            .drain(..std::mem::take(&mut self.env_rules));
+
+        let rules = targets
+            .into_iter()
+            .map(|target| Rule::allow(Some(target), LevelFilter::Trace))
+            .collect();
+
+        self.rules(rules)
+    }
+
+    /// Drop the messages whose target starts with any of the specified prefixes, e.g. noisy
+    /// dependencies. This is checked in addition to `filter_targets` and `rules`.
+    pub fn exclude_targets(mut self, targets: Vec<&str>) -> Self {
+        self.config
+            .excluded_targets
+            .get_or_insert_with(Vec::new)
+            .extend(targets.into_iter().map(|target| target.to_string()));
+
+        self
+    }
+
+    #[cfg(feature = "regex")]
+    /// Only log messages whose target matches any of the regular expressions, e.g. `"::db$"`.
+    /// This is checked in addition to `filter_targets` and `rules`.
+    pub fn filter_targets_regex(mut self, patterns: Vec<&str>) -> Result<Self, FtailError> {
+        let regexes = patterns
+            .into_iter()
+            .map(|pattern| {
+                regex::Regex::new(pattern)
+                    .map_err(|error| FtailError::InvalidRegex(error.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.config
+            .target_regexes
+            .get_or_insert_with(Vec::new)
+            .extend(regexes);
+
+        Ok(self)
+    }
+
+    /// Append filter rules, evaluated in order. The first matching rule decides whether a message is logged.
+    pub fn rules(mut self, rules: Vec<Rule>) -> Self {
+        self.config.rules.extend(rules);
+
+        self
+    }
+
+    /// Flush the file channels every `n` records instead of after every record.
+    /// Warnings and errors are always flushed immediately.
+    pub fn flush_every(mut self, n: u64) -> Self {
+        self.config.flush_every = Some(n.max(1));
+
+        self
+    }
+
+    /// Flush the file channels every `interval` on a background thread instead of after every
+    /// record. Warnings and errors are always flushed immediately.
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.config.flush_interval = Some(interval);
+
+        self
+    }
+
+    /// Force the colors of the formatted console on or off.
+    pub fn force_color(mut self, color: bool) -> Self {
+        self.config.force_color = Some(color);
+
+        self
+    }
+
+    /// Truncate or wrap a field of the default format when it is longer than the given width.
+    /// Only the console wraps, the file channels keep wrapped fields whole on one line.
+    pub fn field_overflow(mut self, field: Field, overflow: Overflow) -> Self {
+        match field {
+            Field::Target => self.config.target_overflow = Some(overflow),
+            Field::Message => self.config.message_overflow = Some(overflow),
+        }
+
+        self
+    }
+
+    /// Set the terminal title from the console channels when a warning or an error is logged, so a
+    /// terminal multiplexer flags the pane. Disabled with `NO_COLOR` or when stdout is not a terminal.
+    pub fn terminal_title_on_error(mut self) -> Self {
+        self.config.terminal_title_on_error = true;
+
+        self
+    }
+
+    /// Stop logging after `n` records. A last warning is logged when the cap is reached.
+    pub fn max_records(mut self, n: u64) -> Self {
+        self.config.max_records = Some(n);
+
+        self
+    }
+
+    /// Run each channel on its own thread with a queue of `capacity` records, so a slow channel does
+    /// not block the others. Logging blocks when the queue of a channel is full, unless the
+    /// `overflow` policy drops records.
+    pub fn async_channels(mut self, capacity: usize) -> Self {
+        self.config.async_capacity = Some(capacity.max(1));
+
+        self
+    }
+
+    /// Write the records of the file channels on a background thread, so logging to a file returns
+    /// immediately. The queue is unbounded unless `queue_capacity` is set, and `flush` waits for it
+    /// to drain.
+    pub fn non_blocking(mut self) -> Self {
+        self.config.non_blocking = true;
+
+        self
+    }
+
+    /// Bound the queue of the `non_blocking` writer to `capacity` records. The `overflow` policy
+    /// applies when it is full.
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.config.queue_capacity = Some(capacity.max(1));
+
+        self
+    }
+
+    /// Set what happens to a record when the queue of the `non_blocking` writer or of an
+    /// `async_channels` channel is full: block the logging thread (the default), or drop the new
+    /// record or the oldest queued one. The dropped records are counted and reported by a warning
+    /// to their channel at most every 10 seconds and when it is flushed.
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.config.overflow = overflow;
+
+        self
+    }
+
+    /// Buffer the console channels per thread and write `batch_size` lines at once to reduce contention on stdout.
+    /// Lines of different threads may be written out of order. A warning or an error writes the
+    /// buffer of its thread at once, and the buffered lines are written by `log::logger().flush()`
+    /// and every `flush_interval`: set one, as the lines still buffered at exit may be lost.
+    pub fn console_batch_size(mut self, batch_size: usize) -> Self {
+        self.config.console_batch_size = Some(batch_size.max(1));
+
+        self
+    }
+
+    /// Write the warnings and errors of the console channels to stderr and the other records to
+    /// stdout, so the diagnostics can be redirected separately. The default is to write all
+    /// records to stdout.
+    pub fn console_split_streams(mut self, split: bool) -> Self {
+        self.config.console_split_streams = split;
+
+        self
+    }
+
+    /// Set the terminator the file channels write after each record. The default is `\n`.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.config.line_ending = line_ending;
+
+        self
+    }
+
+    /// Include the name of the thread that logged the record, or its id when it is unnamed, in
+    /// the default, JSON and logfmt formats, e.g. to debug concurrency issues.
+    pub fn show_thread(mut self, show: bool) -> Self {
+        self.config.show_thread = show;
+
+        self
+    }
+
+    /// Include the id of the process in the default, JSON and logfmt formats, e.g. when several
+    /// instances write to the same directory.
+    pub fn show_pid(mut self, show: bool) -> Self {
+        self.config.show_pid = show;
+
+        self
+    }
+
+    /// Cut the messages longer than `n` characters and mark them with `…[truncated]`, e.g. to keep
+    /// a huge payload out of the log files. The datetime, level and target are kept.
+    pub fn max_message_length(mut self, n: usize) -> Self {
+        self.config.max_message_length = Some(n);
+
+        self
+    }
+
+    /// Cut the values of the key-values longer than `n` characters and mark them with
+    /// `…[truncated]`, so a huge value does not bloat the structured output.
+    pub fn max_kv_value_length(mut self, n: usize) -> Self {
+        self.config.max_kv_value_length = Some(n);
+
+        self
+    }
+
+    /// Only write the first `n` key-values of each record, dropping the others.
+    pub fn max_key_values(mut self, n: usize) -> Self {
+        self.config.max_key_values = Some(n);
+
+        self
+    }
+
+    /// Set the separator printed between records by the formatted console. The default is an empty line.
+    pub fn record_separator(mut self, separator: SepStyle) -> Self {
+        self.config.record_separator = separator;
+
+        self
+    }
+
+    /// Show the target of the records after the level in the formatted console, e.g.
+    /// `INFO · app::db`. The default is to show it.
+    pub fn formatted_target(mut self, show: bool) -> Self {
+        self.config.formatted_target = show;
+
+        self
+    }
+
+    /// Set the colors of the formatted console, e.g. a brighter datetime for dark terminals.
+    pub fn color_theme(mut self, theme: ColorTheme) -> Self {
+        self.config.color_theme = theme;
+
+        self
+    }
+
+    /// Append the backtrace to error messages in the file channels. Capturing a backtrace is expensive.
+    pub fn backtrace_on_error(mut self) -> Self {
+        self.config.backtrace_on_error = true;
+
+        self
+    }
+
+    /// Collapse the identical records (same level, target and message) repeated within the
+    /// window after the first one into a single `last message repeated N times` record, logged
+    /// before the next different record, before the next identical record after the window, or
+    /// when the logger is flushed.
+    pub fn dedup_repeats(mut self, window: Duration) -> Self {
+        self.config.dedup_window = Some(window);
+
+        self
+    }
+
+    /// Log the panics at the error level with the `panic` target when the logger is initialized,
+    /// before the previous panic hook runs.
+    pub fn capture_panics(mut self) -> Self {
+        self.config.capture_panics = true;
+
+        self
+    }
+
+    /// Abort the process after a message at or above the level is logged. Meant for tests and strict CI runs.
+    pub fn abort_on(mut self, level: Level) -> Self {
+        self.config.abort_on = Some(level);
+
+        self
+    }
+
+    /// Only log the 1st, (N+1)th, (2N+1)th... occurrences of each distinct message (same level,
+    /// target and message), with the number of occurrences suppressed since the previous one.
+    pub fn sample_per_message(mut self, n: u64) -> Self {
+        self.config.sample_per_message = Some(n.max(1));
+
+        self
+    }
+
+    /// Produce the final line of the console and file channels with a closure, given the record and its rendered fields.
+    pub fn message_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&log::Record, &RenderedRecord) -> String + Send + Sync + 'static,
+    {
+        self.config.message_transform = Some(Arc::new(transform));
+
+        self
+    }
+
+    /// Format the lines of the single and daily file channels with a closure instead of the
+    /// default format, keeping their rotation and retention.
+    pub fn file_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(&log::Record, &Config) -> String + Send + Sync + 'static,
+    {
+        self.config.file_formatter = Some(Arc::new(formatter));
+
+        self
+    }
+
+    /// Write a closing line to the file channels when they are dropped.
+    pub fn footer<F>(mut self, footer: F) -> Self
+    where
+        F: Fn(&ChannelStats) -> String + Send + Sync + 'static,
+    {
+        self.config.footer = Some(Arc::new(footer));
+
+        self
+    }
+
+    fn add_channel<F>(mut self, constructor: F, level: log::LevelFilter) -> Self
+    where
+        F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
+    {
+        self.channels.push(LogChannel::new(constructor, level));
+        self
+    }
+
+    /// Add a file channel whose path and size can be queried from the handle through `current_file`.
+    fn add_file_channel<F>(
+        mut self,
+        constructor: F,
+        level: log::LevelFilter,
+        current_file: Arc<CurrentFile>,
+    ) -> Self
+    where
+        F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
+    {
+        let mut channel = LogChannel::new(constructor, level);
+        channel.current_file = Some(current_file);
+
+        self.channels.push(channel);
+        self
+    }
+
+    /// Name the last added channel, to enable or disable it at runtime with the handle.
+    pub fn named(mut self, name: &str) -> Self {
+        if let Some(channel) = self.channels.last_mut() {
+            channel.name = Some(name.to_string());
+        }
+
+        self
+    }
+
+    /// Filter the last added channel with its own levels and rules instead of the ones of the
+    /// logger, e.g. to only log the warnings and errors of one target to a file.
+    pub fn filtered(mut self, filter: ChannelFilter) -> Self {
+        if let Some(channel) = self.channels.last_mut() {
+            channel.filter = Some(filter);
+        }
+
+        self
+    }
+
+    /// Only log the records of the last added channel up to the level, e.g. `Level::Warn` to
+    /// log the info messages and warnings to a channel and leave the errors to another one.
+    pub fn up_to(mut self, level: Level) -> Self {
+        if let Some(channel) = self.channels.last_mut() {
+            channel.up_to = Some(level);
+        }
+
+        self
+    }
+
+    /// Set the datetime format of the last added channel instead of the one of the logger, e.g. a
+    /// short time on the console and the full date in a file.
+    pub fn with_datetime_format(mut self, datetime_format: &str) -> Self {
+        if let Some(channel) = self.channels.last_mut() {
+            channel.datetime_format = Some(datetime_format.to_string());
+        }
+
+        self
+    }
+
+    /// Add a channel that logs messages to the console.
+    pub fn console(self, level: log::LevelFilter) -> Self {
+        let constructor =
+            |config: Config| Box::new(ConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>;
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that logs messages to the console as newline-delimited JSON.
+    pub fn console_json(self, level: log::LevelFilter) -> Self {
+        let constructor =
+            |config: Config| Box::new(JsonConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>;
+
+        self.add_channel(constructor, level)
+    }
+
+    #[cfg(feature = "pretty-json")]
+    /// Add a channel that logs messages to the console as indented multi-line JSON, for development.
+    /// The output is not newline-delimited, use `console_json` for machine ingestion.
+    pub fn console_json_pretty(self, level: log::LevelFilter) -> Self {
+        let constructor = |config: Config| {
+            Box::new(JsonConsoleLogger::pretty(config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that logs messages to the console as logfmt.
+    pub fn logfmt_console(self, level: log::LevelFilter) -> Self {
+        let constructor = |config: Config| {
+            Box::new(LogfmtConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that logs formatted messages to the console.
+    pub fn formatted_console(self, level: log::LevelFilter) -> Self {
+        let constructor = |config: Config| {
+            Box::new(FormattedConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that logs messages to a single file.
+    pub fn single_file(self, path: &str, append: bool, level: log::LevelFilter) -> Self {
+        let path = path.to_string();
+
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            Box::new(
+                SingleFileLogger::new(&path, append, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
+    }
+
+    /// Add a channel that logs messages to a single log file as newline-delimited JSON.
+    pub fn json_file(self, path: &str, append: bool, level: log::LevelFilter) -> Self {
+        let path = path.to_string();
+
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            Box::new(
+                SingleFileLogger::json(&path, append, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
+    }
+
+    /// Add a channel that logs messages to a single log file as logfmt.
+    pub fn logfmt_file(self, path: &str, append: bool, level: log::LevelFilter) -> Self {
+        let path = path.to_string();
+
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            Box::new(
+                SingleFileLogger::logfmt(&path, append, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
+    }
+
+    /// Add a channel that logs formatted messages to two files, with the colors in `colored_path`
+    /// (e.g. for `less -R`) and without them in `plain_path`. The files are appended to.
+    pub fn dual_file(self, colored_path: &str, plain_path: &str, level: log::LevelFilter) -> Self {
+        let colored_path = colored_path.to_string();
+        let plain_path = plain_path.to_string();
+
+        let constructor = move |config: Config| {
+            Box::new(DualFileLogger::new(&colored_path, &plain_path, config).unwrap())
+                as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that logs messages to a file that is already open, e.g. locked elsewhere.
+    /// The file is not rotated.
+    pub fn file_handle(self, file: std::fs::File, level: log::LevelFilter) -> Self {
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            let file = file.try_clone().unwrap();
+
+            Box::new(
+                SingleFileLogger::from_file(file, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
+    }
+
+    /// Add a channel that logs messages to any writer, e.g. a `Vec<u8>`, a pipe or a socket.
+    pub fn writer<W>(self, writer: W, level: log::LevelFilter) -> Self
+    where
+        W: std::io::Write + Send + 'static,
+    {
+        let writer = Arc::new(Mutex::new(writer));
+
+        let constructor = move |config: Config| {
+            Box::new(WriterLogger::new(Arc::clone(&writer), config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
     }
 
-    /// Set the maximum file size for the logger.
-    pub fn max_file_size(mut self, max_file_size_in_mb: u64) -> Self {
-        self.config.max_file_size = Some(max_file_size_in_mb * 1024 * 1024);
+    /// Open a log file shared by the views added after it with `view`.
+    pub fn shared_file(mut self, path: &str, append: bool) -> Self {
+        self.shared_file = Some(SharedFile::new(path, append));
 
         self
     }
 
-    /// Set the retention days for the logger (daily file logger only).
-    pub fn retention_days(mut self, retention_days: u64) -> Self {
-        self.config.retention_days = Some(retention_days);
+    /// Add a channel that logs messages to the last shared file with its own format.
+    /// Panics if no shared file was opened before.
+    pub fn view<F>(self, level: log::LevelFilter, formatter: F) -> Self
+    where
+        F: Fn(&log::Record, &Config) -> String + Send + Sync + 'static,
+    {
+        let file = self
+            .shared_file
+            .clone()
+            .expect("view() must be called after shared_file()");
+        let format: RecordFormat = Arc::new(formatter);
 
-        self
+        let constructor = move |config: Config| {
+            Box::new(SharedFileLogger::new(&file, format.clone(), config).unwrap())
+                as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
     }
 
-    /// Only log messages with the specified levels. The default is to log all levels.
-    pub fn filter_levels(mut self, levels: Vec<Level>) -> Self {
-        self.config.levels = Some(levels);
+    /// Add a channel that logs messages to a daily log file as newline-delimited JSON.
+    /// The directory is created when it does not exist.
+    pub fn daily_json_file(self, dir: &str, level: log::LevelFilter) -> Self {
+        let dir = dir.to_string();
 
-        self
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            std::fs::create_dir_all(&dir).unwrap();
+
+            Box::new(
+                DailyFileLogger::json(&dir, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
     }
 
-    /// Only log messages with the specified targets. The default is to log all targets.
-    pub fn filter_targets(mut self, targets: Vec<&str>) -> Self {
-        self.config.targets = Some(targets.iter().map(|s| s.to_string()).collect());
+    /// Add a channel that logs messages to a daily log file.
+    pub fn daily_file(self, path: &str, level: log::LevelFilter) -> Self {
+        let path = path.to_string();
 
-        self
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            Box::new(
+                DailyFileLogger::new(&path, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
     }
 
-    fn add_channel<F>(mut self, constructor: F, level: log::LevelFilter) -> Self
-    where
-        F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
-    {
-        self.channels.push(LogChannel::new(constructor, level));
-        self
+    /// Add a channel that logs messages to daily log files named with a chrono pattern, e.g.
+    /// `app-%Y-%m-%d.log`. A new file is started whenever the formatted name changes, so a pattern
+    /// with the hour starts a new file every hour.
+    pub fn daily_file_pattern(self, dir: &str, pattern: &str, level: log::LevelFilter) -> Self {
+        let dir = dir.to_string();
+        let pattern = pattern.to_string();
+
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            Box::new(
+                DailyFileLogger::with_pattern(&dir, &pattern, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
     }
 
-    /// Add a channel that logs messages to the console.
-    pub fn console(self, level: log::LevelFilter) -> Self {
-        let constructor =
-            |config: Config| Box::new(ConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>;
+    /// Add a channel that logs messages to a new log file every hour.
+    /// For other names, e.g. `YYYY_MM_DD_HH.log` or `YYYYMMDDHH.log`, use `daily_file_pattern`
+    /// with a pattern like `%Y_%m_%d_%H.log`, which also names the rotated and retained files.
+    pub fn hourly_file(self, dir: &str, level: log::LevelFilter) -> Self {
+        let dir = dir.to_string();
+
+        let current_file = Arc::new(CurrentFile::default());
+        let shared = Arc::clone(&current_file);
+
+        let constructor = move |config: Config| {
+            Box::new(
+                HourlyFileLogger::new(&dir, config)
+                    .unwrap()
+                    .with_current_file(Arc::clone(&shared)),
+            ) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_file_channel(constructor, level, current_file)
+    }
+
+    /// Add a channel that logs messages across `n` files (`part0.log`..`part{n-1}.log`) in a directory.
+    /// The next file is truncated and written to when the current one exceeds `max_size_in_mb`.
+    pub fn striped_files(
+        self,
+        dir: &str,
+        n: usize,
+        max_size_in_mb: u64,
+        level: log::LevelFilter,
+    ) -> Self {
+        let dir = dir.to_string();
+        let max_size = max_size_in_mb * 1024 * 1024;
+
+        let constructor = move |config: Config| {
+            Box::new(StripedFilesLogger::new(&dir, n, max_size, config).unwrap())
+                as Box<dyn Log + Send + Sync>
+        };
 
         self.add_channel(constructor, level)
     }
 
-    /// Add a channel that logs formatted messages to the console.
-    pub fn formatted_console(self, level: log::LevelFilter) -> Self {
-        let constructor = |config: Config| {
-            Box::new(FormattedConsoleLogger::new(config)) as Box<dyn Log + Send + Sync>
+    #[cfg(feature = "elasticsearch")]
+    /// Add a channel that indexes the records in Elasticsearch with the `_bulk` API, in batches sent
+    /// by a background thread. The URL is a plain HTTP URL, e.g. `http://localhost:9200`.
+    pub fn elasticsearch(self, url: &str, index: &str, level: log::LevelFilter) -> Self {
+        let url = url.to_string();
+        let index = index.to_string();
+
+        let constructor = move |config: Config| {
+            Box::new(
+                channels::elasticsearch::ElasticsearchLogger::new(&url, &index, config).unwrap(),
+            ) as Box<dyn Log + Send + Sync>
         };
 
         self.add_channel(constructor, level)
     }
 
-    /// Add a channel that logs messages to a single file.
-    pub fn single_file(self, path: &str, append: bool, level: log::LevelFilter) -> Self {
-        let path = path.to_string();
+    #[cfg(feature = "gelf")]
+    /// Add a channel that sends GELF (Graylog) messages over UDP.
+    pub fn gelf(self, addr: &str, level: log::LevelFilter) -> Self {
+        let addr = addr.to_string();
 
         let constructor = move |config: Config| {
-            Box::new(SingleFileLogger::new(&path, append, config).unwrap())
+            Box::new(channels::gelf::GelfLogger::new(&addr, config).unwrap())
                 as Box<dyn Log + Send + Sync>
         };
 
         self.add_channel(constructor, level)
     }
 
-    /// Add a channel that logs messages to a daily log file.
-    pub fn daily_file(self, path: &str, level: log::LevelFilter) -> Self {
+    #[cfg(feature = "sqlite")]
+    /// Add a channel that inserts the records into a SQLite database, creating the `logs` table if missing.
+    pub fn sqlite(self, path: &str, level: log::LevelFilter) -> Self {
         let path = path.to_string();
 
         let constructor = move |config: Config| {
-            Box::new(DailyFileLogger::new(&path, config).unwrap()) as Box<dyn Log + Send + Sync>
+            Box::new(channels::sqlite::SqliteLogger::new(&path, config).unwrap())
+                as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    #[cfg(feature = "syslog")]
+    /// Add a channel that sends RFC 5424 syslog messages to `udp://host:port` or `tcp://host:port`
+    /// (UDP when there is no scheme), with the syslog `facility`, e.g. `16` for local0.
+    pub fn syslog(self, addr: &str, facility: u8, level: log::LevelFilter) -> Self {
+        let addr = addr.to_string();
+
+        let constructor = move |config: Config| {
+            Box::new(channels::syslog::SyslogLogger::new(&addr, facility, config).unwrap())
+                as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    #[cfg(feature = "tcp")]
+    /// Add a channel that streams formatted messages to a TCP server, e.g. `aggregator:5170`, one
+    /// line per message. The connection is opened again on the next message after an error.
+    pub fn tcp(self, addr: &str, level: log::LevelFilter) -> Self {
+        let addr = addr.to_string();
+
+        let constructor = move |config: Config| {
+            Box::new(channels::tcp::TcpLogger::new(&addr, config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    #[cfg(all(windows, feature = "windows"))]
+    /// Add a channel that sends messages to the attached debugger with `OutputDebugStringW`.
+    pub fn debug_output(self, level: log::LevelFilter) -> Self {
+        let constructor = |config: Config| {
+            Box::new(channels::debug_output::DebugOutputLogger::new(config))
+                as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that sends rendered records to an mpsc channel, e.g. to display them in the event loop of the application.
+    /// The sender can be a `Sender` or a `SyncSender`, records are dropped when a `SyncSender` is full.
+    pub fn mpsc<S>(self, sender: S, level: log::LevelFilter) -> Self
+    where
+        S: Into<RecordSender>,
+    {
+        let sender = sender.into();
+
+        let constructor = move |config: Config| {
+            Box::new(MpscLogger::new(sender.clone(), config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that sends rendered records to the subscribers of a broadcast, e.g. to stream
+    /// them to a live log viewer. Subscribers that fall behind miss records instead of blocking.
+    pub fn broadcast(self, broadcast: &Broadcast, level: log::LevelFilter) -> Self {
+        let broadcast = broadcast.clone();
+
+        let constructor = move |config: Config| {
+            Box::new(BroadcastLogger::new(broadcast.clone(), config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that keeps the last formatted lines in a memory buffer, e.g. to show them in
+    /// an admin panel. The oldest line is dropped when the buffer is full.
+    pub fn memory(self, buffer: &MemoryBuffer, level: log::LevelFilter) -> Self {
+        let buffer = buffer.clone();
+
+        let constructor = move |config: Config| {
+            Box::new(MemoryLogger::new(buffer.clone(), config)) as Box<dyn Log + Send + Sync>
+        };
+
+        self.add_channel(constructor, level)
+    }
+
+    /// Add a channel that calls the closure for each record, e.g. for an ad-hoc integration
+    /// without implementing the `Log` trait.
+    pub fn callback<F>(self, callback: F, level: log::LevelFilter) -> Self
+    where
+        F: Fn(&log::Record, &Config) + Send + Sync + 'static,
+    {
+        let callback: channels::callback::RecordCallback = Arc::new(callback);
+
+        let constructor = move |config: Config| {
+            Box::new(CallbackLogger::new(Arc::clone(&callback), config))
+                as Box<dyn Log + Send + Sync>
         };
 
         self.add_channel(constructor, level)
@@ -349,94 +1950,475 @@ impl Ftail {
         self.add_channel(constructor, level)
     }
 
-    /// Initialize the logger.
-    pub fn init(mut self) -> Result<(), FtailError> {
+    /// Initialize the logger. The returned handle can be used to change the logger afterwards.
+    pub fn init(self) -> Result<FtailHandle, FtailError> {
+        let ftail = self.build()?;
+        let handle = ftail.handle().installed();
+
+        let max_level = ftail.state.read().unwrap().max_level();
+        let capture_panics = ftail.config.capture_panics;
+
+        log::set_boxed_logger(Box::new(ftail)).map_err(FtailError::SetLoggerError)?;
+        log::set_max_level(max_level);
+
+        if capture_panics {
+            panic::install_hook();
+        }
+
+        Ok(handle)
+    }
+
+    pub(crate) fn handle(&self) -> FtailHandle {
+        FtailHandle::new(Arc::clone(&self.state))
+    }
+
+    /// Take the state of a built logger, leaving it without channels.
+    pub(crate) fn into_state(self) -> LoggerState {
+        std::mem::take(&mut *self.state.write().unwrap())
+    }
+
+    /// Initialize the channels without installing the logger globally.
+    pub(crate) fn build(self) -> Result<Self, FtailError> {
+        let ftail = self.build_with(WriteErrors::default())?;
+        spawn_flush_timer(&ftail.state)?;
+
+        Ok(ftail)
+    }
+
+    /// Initialize the channels, reporting their failed writes to `write_errors`, e.g. the ones
+    /// of the logger being reconfigured. The flush timer is not started.
+    pub(crate) fn build_with(mut self, write_errors: WriteErrors) -> Result<Self, FtailError> {
         if self.channels.is_empty() {
             return Err(FtailError::NoChannelsError);
         }
 
+        let formats = self
+            .channels
+            .iter()
+            .filter_map(|channel| channel.datetime_format.as_ref());
+
+        if let Some(format) = std::iter::once(&self.config.datetime_format)
+            .chain(formats)
+            .find(|format| {
+                chrono::format::StrftimeItems::new(format)
+                    .any(|item| item == chrono::format::Item::Error)
+            })
+        {
+            return Err(FtailError::InvalidDatetimeFormat(format.clone()));
+        }
+
         let channels = std::mem::take(&mut self.channels);
+        let writer = self
+            .config
+            .non_blocking
+            .then(|| BackgroundWriter::new(&self.config));
 
-        self.initialized_channels = channels
-            .into_iter()
-            .map(|channel| {
-                let mut config = self.config.clone();
-                config.level_filter = channel.level;
+        self.state = Arc::new(RwLock::new(LoggerState {
+            channels: channels
+                .into_iter()
+                .map(|channel| {
+                    write_errors.collect(|| channel.init(self.config.clone(), writer.as_ref()))
+                })
+                .collect(),
+            writer,
+            records: AtomicU64::new(0),
+            samples: Samples::default(),
+            repeats: Repeats::default(),
+            write_errors,
+            flush_timer: Arc::default(),
+            level: AtomicUsize::new(0),
+            target_filter: None,
+            config: self.config.clone(),
+        }));
 
-                channel.init(config)
-            })
-            .collect();
+        Ok(self)
+    }
+}
 
-        log::set_max_level(log::LevelFilter::Trace);
-        log::set_boxed_logger(Box::new(self)).map_err(FtailError::SetLoggerError)
+impl InitializedLogChannel {
+    /// Whether the level is not more severe than the `up_to` level of the channel.
+    fn is_within_up_to(&self, level: Level) -> bool {
+        self.up_to.map_or(true, |up_to| level >= up_to)
     }
 }
 
 impl LogChannel {
-    fn new<F>(constructor: F, level: log::LevelFilter) -> Self
+    pub(crate) fn new<F>(constructor: F, level: log::LevelFilter) -> Self
     where
         F: Fn(Config) -> Box<dyn Log + Send + Sync> + 'static,
     {
         Self {
             constructor: Box::new(constructor),
             level,
+            name: None,
+            filter: None,
+            up_to: None,
+            datetime_format: None,
+            current_file: None,
         }
     }
 
-    fn init(self, config: Config) -> InitializedLogChannel {
+    /// Construct the channel. The file channels are written by the `writer` when given, the
+    /// other channels run on their own thread with `async_channels`.
+    pub(crate) fn init(
+        self,
+        mut config: Config,
+        writer: Option<&BackgroundWriter>,
+    ) -> InitializedLogChannel {
+        // the logger filters the records by the level of the channel, which can be set at runtime
+        config.level_filter = LevelFilter::Trace;
+
+        if let Some(datetime_format) = self.datetime_format {
+            config.datetime_format = datetime_format;
+        }
+
+        let channel: Box<dyn Log + Send + Sync> = match (writer, config.async_capacity) {
+            (Some(writer), _) if self.current_file.is_some() => Box::new(NonBlockingLogger::new(
+                (self.constructor)(config.clone()),
+                writer.clone(),
+                config,
+            )),
+            (_, Some(capacity)) => Box::new(QueuedLogger::new(
+                (self.constructor)(config.clone()),
+                capacity,
+                config,
+            )),
+            (_, None) => (self.constructor)(config),
+        };
+
         InitializedLogChannel {
-            channel: (self.constructor)(config),
+            channel,
+            level: self.level,
+            name: self.name,
+            enabled: AtomicBool::new(true),
+            filter: self.filter,
+            up_to: self.up_to,
+            current_file: self.current_file,
         }
     }
 }
 
-impl Log for Ftail {
+impl LoggerState {
+    /// The most verbose level any channel logs, for `log::set_max_level`, so the macros of the
+    /// other levels return before formatting their arguments.
+    pub(crate) fn max_level(&self) -> LevelFilter {
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| {
+                let levels = match &channel.filter {
+                    Some(filter) => filter.levels.as_deref(),
+                    None => self.config.levels.as_deref(),
+                };
+
+                self.channel_level(channel)
+                    .min(rules::most_verbose_level(levels))
+            })
+            .max()
+            .unwrap_or(LevelFilter::Off);
+
+        channels.min(self.level_cap().unwrap_or(LevelFilter::Trace))
+    }
+
+    /// The level set with `FtailHandle::set_level`, if any.
+    pub(crate) fn runtime_level(&self) -> Option<LevelFilter> {
+        match self.level.load(Ordering::Relaxed) {
+            0 => None,
+            level => LevelFilter::iter().nth(level - 1),
+        }
+    }
+
+    pub(crate) fn set_runtime_level(&self, level: LevelFilter) {
+        self.level.store(level as usize + 1, Ordering::Relaxed);
+    }
+
+    /// The most verbose level of the channel, unless the level was set at runtime.
+    fn channel_level(&self, channel: &InitializedLogChannel) -> LevelFilter {
+        self.runtime_level().unwrap_or(channel.level)
+    }
+
+    /// The most verbose level of the logger, unless the level was set at runtime.
+    fn level_cap(&self) -> Option<LevelFilter> {
+        self.runtime_level().or(self.config.max_level)
+    }
+
+    /// Whether any channel accepts the record, with its own filter or the filters of the logger.
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        if self.config.levels.is_some()
-            && !self
-                .config
-                .levels
-                .as_ref()
-                .unwrap()
-                .contains(&metadata.level())
+        if self
+            .level_cap()
+            .is_some_and(|level| metadata.level() > level)
         {
             return false;
         }
 
-        if self.config.targets.is_some()
-            && !self
-                .config
-                .targets
-                .as_ref()
-                .unwrap()
-                .iter()
-                .any(|t| metadata.target().to_string().starts_with(t))
-        {
+        if !self.is_in_target_filter(metadata) {
             return false;
         }
 
+        self.is_allowed(metadata)
+            || self
+                .channels
+                .iter()
+                .any(|channel| self.accepts(channel, metadata))
+    }
+
+    /// Whether the filters of the logger allow the record.
+    fn is_allowed(&self, metadata: &log::Metadata) -> bool {
+        rules::is_level_allowed(self.config.levels.as_deref(), metadata)
+            && self.is_target_listed(metadata)
+            && !rules::is_excluded(self.config.excluded_targets.as_deref(), metadata)
+            && self.matches_target_regexes(metadata)
+            && rules::is_allowed(&self.config.rules, metadata)
+    }
+
+    /// Whether the target starts with any of the targets of `set_target_filter`, when it is set.
+    fn is_in_target_filter(&self, metadata: &log::Metadata) -> bool {
+        self.target_filter.as_ref().map_or(true, |targets| {
+            targets
+                .iter()
+                .any(|target| metadata.target().starts_with(target.as_str()))
+        })
+    }
+
+    /// Whether the target is in the deprecated `targets` allow-list, when it is set.
+    #[allow(deprecated)]
+    fn is_target_listed(&self, metadata: &log::Metadata) -> bool {
+        self.config.targets.as_ref().map_or(true, |targets| {
+            targets
+                .iter()
+                .any(|target| metadata.target().starts_with(target.as_str()))
+        })
+    }
+
+    #[cfg(feature = "regex")]
+    fn matches_target_regexes(&self, metadata: &log::Metadata) -> bool {
+        self.config.target_regexes.as_ref().map_or(true, |regexes| {
+            regexes
+                .iter()
+                .any(|regex| regex.is_match(metadata.target()))
+        })
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn matches_target_regexes(&self, _metadata: &log::Metadata) -> bool {
         true
     }
 
+    fn accepts(&self, channel: &InitializedLogChannel, metadata: &log::Metadata) -> bool {
+        if !channel.is_within_up_to(metadata.level()) {
+            return false;
+        }
+
+        match &channel.filter {
+            Some(filter) => filter.is_allowed(metadata),
+            None => self.is_allowed(metadata),
+        }
+    }
+
     fn log(&self, record: &log::Record) {
         if !self.enabled(record.metadata()) {
             return;
         }
 
-        for channel in &self.initialized_channels {
-            channel.channel.log(record);
+        if let Some(window) = self.config.dedup_window {
+            match self.repeats.check(record, window, &self.config) {
+                Some(ended) => self.log_repeated(ended),
+                None => return,
+            }
+        }
+
+        let suppressed = match self
+            .config
+            .sample_per_message
+            .map(|every| self.samples.check(record, every))
+        {
+            Some(None) => return,
+            Some(Some(suppressed)) => suppressed,
+            None => 0,
+        };
+
+        if let Some(max_records) = self.config.max_records {
+            let records = self.records.fetch_add(1, Ordering::Relaxed);
+
+            if records >= max_records {
+                if records == max_records {
+                    self.dispatch(
+                        false,
+                        &log::Record::builder()
+                            .args(format_args!(
+                                "record cap of {} reached, further records are dropped",
+                                max_records
+                            ))
+                            .level(Level::Warn)
+                            .target("ftail")
+                            .build(),
+                    );
+                }
+
+                return;
+            }
+        }
+
+        match suppressed {
+            0 => self.dispatch(true, record),
+            _ => self.dispatch(
+                true,
+                &log::Record::builder()
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .args(format_args!(
+                        "{} ({} similar messages suppressed)",
+                        record.args(),
+                        suppressed
+                    ))
+                    .build(),
+            ),
+        }
+
+        if self
+            .config
+            .abort_on
+            .is_some_and(|level| record.level() <= level)
+        {
+            self.flush();
+            std::process::abort();
         }
     }
 
-    fn flush(&self) {
-        for channel in &self.initialized_channels {
+    /// Log the record to the enabled channels, only to the ones that accept it when `filtered`.
+    fn dispatch(&self, filtered: bool, record: &log::Record) {
+        for channel in &self.channels {
+            if channel.enabled.load(Ordering::Relaxed)
+                && record.level() <= self.channel_level(channel)
+                && channel.is_within_up_to(record.level())
+                && (!filtered || self.accepts(channel, record.metadata()))
+            {
+                channel.channel.log(record);
+            }
+        }
+    }
+
+    /// Log the number of times the last record was repeated, at its level and with its target.
+    fn log_repeated(&self, repeated: Option<Repeated>) {
+        let Some(repeated) = repeated else {
+            return;
+        };
+
+        self.dispatch(
+            true,
+            &log::Record::builder()
+                .args(format_args!(
+                    "last message repeated {} times",
+                    repeated.times
+                ))
+                .level(repeated.level)
+                .target(&repeated.target)
+                .build(),
+        );
+    }
+
+    pub(crate) fn flush(&self) {
+        self.log_repeated(self.repeats.take());
+
+        for channel in &self.channels {
             channel.channel.flush();
         }
     }
 }
 
+/// Flush the channels every `flush_interval` of the installed state on a background thread, until
+/// the logger is dropped or the state is replaced by `reconfigure`, which starts its own timer.
+pub(crate) fn spawn_flush_timer(state: &Arc<RwLock<LoggerState>>) -> Result<(), FtailError> {
+    let (interval, timer) = {
+        let state = state.read().unwrap();
+
+        match state.config.flush_interval {
+            Some(interval) => (interval, Arc::downgrade(&state.flush_timer)),
+            None => return Ok(()),
+        }
+    };
+    let state = Arc::downgrade(state);
+
+    std::thread::Builder::new()
+        .name("ftail-flush".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+
+            let Some(state) = state.upgrade() else {
+                return;
+            };
+            let state = state.read().unwrap();
+
+            if timer.strong_count() == 0 {
+                return;
+            }
+
+            state.flush();
+        })
+        .map_err(FtailError::IoError)?;
+
+    Ok(())
+}
+
+thread_local! {
+    /// Whether this thread holds the state of the logger to log or flush.
+    static IN_LOGGER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` on the state of the logger, unless this thread is already inside the logger, e.g. a
+/// callback channel or the panic hook logging while a record is logged. The nested record is
+/// dropped: the state is already locked for reading, and locking it again waits for any thread
+/// waiting to lock it for writing (`reconfigure`, `close`...), which waits for this thread.
+pub(crate) fn with_state<R>(
+    state: &RwLock<LoggerState>,
+    f: impl FnOnce(&LoggerState) -> R,
+) -> Option<R> {
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            IN_LOGGER.set(false);
+        }
+    }
+
+    if IN_LOGGER.replace(true) {
+        return None;
+    }
+
+    // reset on a panic of a channel too, so the thread can still log
+    let _reset = Reset;
+
+    Some(f(&state.read().unwrap()))
+}
+
+impl Log for Ftail {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        with_state(&self.state, |state| state.enabled(metadata)).unwrap_or(false)
+    }
+
+    fn log(&self, record: &log::Record) {
+        with_state(&self.state, |state| state.log(record));
+    }
+
+    fn flush(&self) {
+        with_state(&self.state, |state| state.flush());
+    }
+}
+
 impl Default for Ftail {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Format a record the same way as the built-in channels do, e.g. to include it in an error report.
+pub fn format_record(record: &log::Record, config: &Config) -> String {
+    DefaultFormatter::new(record, config).format()
+}
+
+/// Format a record the same way as the formatted console does, including the escape codes of the colors.
+pub fn format_record_readable(record: &log::Record, config: &Config) -> String {
+    ReadableFormatter::new(record, config).format()
+}
@@ -0,0 +1,67 @@
+//! Logging of the panics, installed by `init` with `capture_panics`.
+
+use std::{any::Any, cell::Cell, panic::PanicHookInfo};
+
+thread_local! {
+    /// Whether the hook is logging a panic on this thread, so a panic of the logger is not logged.
+    static IN_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Log the panics at the error level with the `panic` target, then run the previous hook, so the
+/// panic is still printed and `panic = "abort"` still aborts.
+pub(crate) fn install_hook() {
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        if !IN_HOOK.replace(true) {
+            log_panic(info);
+            IN_HOOK.set(false);
+        }
+
+        previous(info);
+    }));
+}
+
+fn log_panic(info: &PanicHookInfo) {
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+    let message = payload_message(info.payload());
+
+    let mut builder = log::Record::builder();
+    builder.level(log::Level::Error).target("panic");
+
+    if let Some(location) = info.location() {
+        builder
+            .file(Some(location.file()))
+            .line(Some(location.line()));
+
+        log::logger().log(
+            &builder
+                .args(format_args!(
+                    "thread '{}' panicked at {}: {}",
+                    thread, location, message
+                ))
+                .build(),
+        );
+    } else {
+        log::logger().log(
+            &builder
+                .args(format_args!("thread '{}' panicked: {}", thread, message))
+                .build(),
+        );
+    }
+
+    // the process may be about to exit or abort
+    log::logger().flush();
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message;
+    }
+
+    match payload.downcast_ref::<String>() {
+        Some(message) => message,
+        None => "Box<dyn Any>",
+    }
+}
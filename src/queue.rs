@@ -0,0 +1,200 @@
+//! The queues of the `async_channels` and `non_blocking` workers, and what to do with a record
+//! when they are full.
+
+use log::{Level, Log};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::helpers::lock;
+
+/// The minimum time between two warnings reporting the records dropped by a queue.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What to do with a record when the queue of a channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Block the logging thread until the queue has room for the record.
+    #[default]
+    Block,
+    /// Drop the record being logged, keeping the queued records.
+    DropNewest,
+    /// Drop the oldest queued record to make room for the record being logged.
+    DropOldest,
+}
+
+/// A queue of records, bounded or not, shared by the logging threads and a worker. The control
+/// messages, e.g. a flush, are never dropped and do not count towards the capacity.
+pub(crate) struct Queue<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+}
+
+struct State<T> {
+    messages: VecDeque<(T, bool)>,
+    records: usize,
+    closed: bool,
+}
+
+impl<T> Queue<T> {
+    pub fn new(capacity: Option<usize>, overflow: OverflowPolicy) -> Self {
+        Queue {
+            state: Mutex::new(State {
+                messages: VecDeque::new(),
+                records: 0,
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: capacity.map(|capacity| capacity.max(1)),
+            overflow,
+        }
+    }
+
+    /// Queue a record, applying the overflow policy when the queue is full. Returns the record
+    /// that was dropped to respect the capacity, either this one or the oldest queued one.
+    pub fn push(&self, record: T) -> Option<T> {
+        let mut state = lock(&self.state);
+
+        if let Some(capacity) = self.capacity {
+            while state.records >= capacity && !state.closed {
+                match self.overflow {
+                    OverflowPolicy::Block => {
+                        state = self
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner)
+                    }
+                    OverflowPolicy::DropNewest => return Some(record),
+                    OverflowPolicy::DropOldest => {
+                        let oldest = state
+                            .messages
+                            .iter()
+                            .position(|(_, is_record)| *is_record)?;
+                        let (oldest, _) = state.messages.remove(oldest)?;
+
+                        state.messages.push_back((record, true));
+                        self.not_empty.notify_one();
+
+                        return Some(oldest);
+                    }
+                }
+            }
+        }
+
+        if state.closed {
+            return Some(record);
+        }
+
+        state.messages.push_back((record, true));
+        state.records += 1;
+        self.not_empty.notify_one();
+
+        None
+    }
+
+    /// Queue a control message, whatever the number of queued records. Returns `false` when the
+    /// queue is closed.
+    pub fn push_control(&self, message: T) -> bool {
+        let mut state = lock(&self.state);
+
+        if state.closed {
+            return false;
+        }
+
+        state.messages.push_back((message, false));
+        self.not_empty.notify_one();
+
+        true
+    }
+
+    /// Wait for the next message, or `None` once the queue is closed and drained.
+    pub fn pop(&self) -> Option<T> {
+        let mut state = lock(&self.state);
+
+        loop {
+            if let Some((message, is_record)) = state.messages.pop_front() {
+                if is_record {
+                    state.records -= 1;
+                    self.not_full.notify_one();
+                }
+
+                return Some(message);
+            }
+
+            if state.closed {
+                return None;
+            }
+
+            state = self
+                .not_empty
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Stop accepting messages. The worker still receives the queued ones.
+    pub fn close(&self) {
+        lock(&self.state).closed = true;
+
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+/// The number of records of a channel dropped by a full queue, reported by a warning logged to the
+/// channel at most once per `REPORT_INTERVAL`.
+pub(crate) struct Dropped {
+    count: AtomicU64,
+    reported_at: Mutex<Instant>,
+}
+
+impl Dropped {
+    pub fn new() -> Self {
+        Dropped {
+            count: AtomicU64::new(0),
+            reported_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn add(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Log a warning with the number of records dropped since the last one, when the interval has
+    /// passed since that warning or when `force`, e.g. on a flush.
+    pub fn report(&self, channel: &dyn Log, force: bool) {
+        let mut reported_at = lock(&self.reported_at);
+
+        if !force && reported_at.elapsed() < REPORT_INTERVAL {
+            return;
+        }
+
+        *reported_at = Instant::now();
+
+        let dropped = self.count.swap(0, Ordering::Relaxed);
+
+        if dropped == 0 {
+            return;
+        }
+
+        channel.log(
+            &log::Record::builder()
+                .args(format_args!(
+                    "{} records were dropped because the queue was full",
+                    dropped
+                ))
+                .level(Level::Warn)
+                .target("ftail")
+                .build(),
+        );
+    }
+}
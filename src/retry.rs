@@ -0,0 +1,150 @@
+use std::{
+    hash::{BuildHasher, Hasher},
+    io::{BufWriter, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// How the network channels retry a failed send or connection: exponential backoff with jitter,
+/// so many processes losing the same server do not all reconnect at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The delay before the first retry.
+    pub base_delay: Duration,
+    /// The maximum delay between two attempts.
+    pub max_delay: Duration,
+    /// The factor the delay grows by after each retry.
+    pub multiplier: f64,
+    /// The fraction of the delay, between 0 and 1, that is randomly taken off each delay.
+    pub jitter: f64,
+    /// The number of attempts, including the first one, before giving up.
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the given retry, `1` being the first retry.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let growth = self.multiplier.max(1.0).powi(exponent);
+
+        let delay = match self.base_delay.is_zero() {
+            true => 0.0,
+            false => (self.base_delay.as_secs_f64() * growth).min(self.max_delay.as_secs_f64()),
+        };
+
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_fraction();
+
+        Duration::from_secs_f64(delay * (1.0 - jitter))
+    }
+}
+
+/// A random number between 0 and 1, from the random keys of the standard library's hasher.
+fn random_fraction() -> f64 {
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A TCP connection that is opened again on the next write after an error, instead of failing the
+/// logger. After a failed connect, the writes fail without connecting until the delay of the retry
+/// policy has passed, so a server that is down does not block every record.
+#[cfg_attr(not(any(feature = "syslog", feature = "tcp")), allow(dead_code))]
+pub(crate) struct Connection {
+    addr: String,
+    stream: Option<BufWriter<TcpStream>>,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+#[cfg_attr(not(any(feature = "syslog", feature = "tcp")), allow(dead_code))]
+impl Connection {
+    pub fn new(addr: &str) -> Self {
+        Connection {
+            addr: addr.to_string(),
+            stream: None,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Write the data, connecting first when there is no connection. A write that fails on a
+    /// connection that was already open is tried once more on a new connection.
+    pub fn write(&mut self, data: &[u8], flush: bool, policy: &RetryPolicy) -> std::io::Result<()> {
+        let connected = self.stream.is_some();
+
+        match self.try_write(data, flush, policy) {
+            Err(_) if connected => self.try_write(data, flush, policy),
+            result => result,
+        }
+    }
+
+    #[cfg(feature = "tcp")]
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        let Some(stream) = &mut self.stream else {
+            return Ok(());
+        };
+
+        let result = stream.flush();
+
+        if result.is_err() {
+            self.stream = None;
+        }
+
+        result
+    }
+
+    fn try_write(&mut self, data: &[u8], flush: bool, policy: &RetryPolicy) -> std::io::Result<()> {
+        let stream = self.connect(policy)?;
+
+        let result = stream
+            .write_all(data)
+            .and_then(|()| if flush { stream.flush() } else { Ok(()) });
+
+        if result.is_err() {
+            self.stream = None;
+        }
+
+        result
+    }
+
+    fn connect(&mut self, policy: &RetryPolicy) -> std::io::Result<&mut BufWriter<TcpStream>> {
+        if self.stream.is_none() {
+            if self.retry_at.is_some_and(|at| Instant::now() < at) {
+                return Err(std::io::Error::other(format!(
+                    "Not connected to {}, waiting to reconnect",
+                    self.addr
+                )));
+            }
+
+            match TcpStream::connect(&self.addr) {
+                Ok(stream) => {
+                    self.failures = 0;
+                    self.retry_at = None;
+                    self.stream = Some(BufWriter::new(stream));
+                }
+                Err(error) => {
+                    self.failures = self.failures.saturating_add(1);
+                    self.retry_at = Some(Instant::now() + policy.delay(self.failures));
+
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(self.stream.as_mut().unwrap())
+    }
+}
@@ -0,0 +1,123 @@
+use log::{Level, LevelFilter, Metadata};
+
+/// A filter rule, evaluated in order by the logger.
+///
+/// A rule matches a record when the target of the record starts with `target_prefix` (any target
+/// when `None`) and the level of the record is within `level`. The first matching rule decides
+/// whether the record is logged. When no rule matches, the record is logged unless there is at
+/// least one `Allow` rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub target_prefix: Option<String>,
+    pub level: LevelFilter,
+    pub action: RuleAction,
+}
+
+/// The action of a filter rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+impl Rule {
+    /// Create a rule that logs the matching records.
+    pub fn allow(target_prefix: Option<&str>, level: LevelFilter) -> Self {
+        Rule {
+            target_prefix: target_prefix.map(|prefix| prefix.to_string()),
+            level,
+            action: RuleAction::Allow,
+        }
+    }
+
+    /// Create a rule that drops the matching records.
+    pub fn deny(target_prefix: Option<&str>, level: LevelFilter) -> Self {
+        Rule {
+            target_prefix: target_prefix.map(|prefix| prefix.to_string()),
+            level,
+            action: RuleAction::Deny,
+        }
+    }
+
+    fn matches(&self, metadata: &Metadata) -> bool {
+        if let Some(prefix) = &self.target_prefix {
+            if !metadata.target().starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+
+        metadata.level() <= self.level
+    }
+}
+
+/// The filters of a single channel, used by the channel instead of the filters of the logger
+/// (`filter_levels`, `filter_targets` and `rules`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelFilter {
+    pub levels: Option<Vec<Level>>,
+    pub rules: Vec<Rule>,
+}
+
+impl ChannelFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only log messages with the specified levels.
+    pub fn levels(mut self, levels: Vec<Level>) -> Self {
+        self.levels = Some(levels);
+
+        self
+    }
+
+    /// Only log messages with the specified targets. This appends an `Allow` rule for each target.
+    pub fn targets(self, targets: Vec<&str>) -> Self {
+        let rules = targets
+            .into_iter()
+            .map(|target| Rule::allow(Some(target), LevelFilter::Trace))
+            .collect();
+
+        self.rules(rules)
+    }
+
+    /// Filter messages with ordered rules, the first matching rule decides.
+    pub fn rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules.extend(rules);
+
+        self
+    }
+
+    pub(crate) fn is_allowed(&self, metadata: &Metadata) -> bool {
+        is_level_allowed(self.levels.as_deref(), metadata) && is_allowed(&self.rules, metadata)
+    }
+}
+
+/// The most verbose of the levels, `Trace` when all the levels are allowed.
+pub(crate) fn most_verbose_level(levels: Option<&[Level]>) -> LevelFilter {
+    match levels {
+        Some(levels) => levels
+            .iter()
+            .max()
+            .map_or(LevelFilter::Off, |level| level.to_level_filter()),
+        None => LevelFilter::Trace,
+    }
+}
+
+pub(crate) fn is_level_allowed(levels: Option<&[Level]>, metadata: &Metadata) -> bool {
+    levels.map_or(true, |levels| levels.contains(&metadata.level()))
+}
+
+pub(crate) fn is_excluded(targets: Option<&[String]>, metadata: &Metadata) -> bool {
+    targets.is_some_and(|targets| {
+        targets
+            .iter()
+            .any(|target| metadata.target().starts_with(target.as_str()))
+    })
+}
+
+pub(crate) fn is_allowed(rules: &[Rule], metadata: &Metadata) -> bool {
+    match rules.iter().find(|rule| rule.matches(metadata)) {
+        Some(rule) => rule.action == RuleAction::Allow,
+        None => !rules.iter().any(|rule| rule.action == RuleAction::Allow),
+    }
+}
@@ -0,0 +1,63 @@
+//! The sampling of the repeated messages with `sample_per_message`.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt::Write,
+    hash::{Hash, Hasher},
+    sync::Mutex,
+};
+
+/// The maximum number of distinct messages counted. The counts start over when it is reached, so
+/// messages with ever changing contents do not grow the map without limit.
+const MAX_MESSAGES: usize = 10_000;
+
+/// The number of times each distinct message (same level, target and message) was logged.
+#[derive(Default)]
+pub(crate) struct Samples(Mutex<HashMap<u64, u64>>);
+
+impl Samples {
+    /// Count the record and decide whether it is logged: the 1st, (N+1)th, (2N+1)th... occurrences
+    /// of a message are, with the number of occurrences suppressed since the previous one, and the
+    /// others return `None`.
+    pub fn check(&self, record: &log::Record, every: u64) -> Option<u64> {
+        let key = key(record);
+        let mut counts = self.0.lock().unwrap_or_else(|e| e.into_inner());
+
+        if counts.len() >= MAX_MESSAGES && !counts.contains_key(&key) {
+            counts.clear();
+        }
+
+        let count = counts.entry(key).or_insert(0);
+        let seen = *count;
+        *count += 1;
+
+        match (seen % every, seen) {
+            (0, 0) => Some(0),
+            (0, _) => Some(every - 1),
+            _ => None,
+        }
+    }
+}
+
+/// A hash of the level, target and message of the record. The message is hashed as it is
+/// formatted, so it is not copied, and a `Display` implementation that fails only ends it early.
+fn key(record: &log::Record) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    record.level().hash(&mut hasher);
+    record.target().hash(&mut hasher);
+
+    let _ = HashWriter(&mut hasher).write_fmt(*record.args());
+
+    hasher.finish()
+}
+
+struct HashWriter<'a>(&'a mut DefaultHasher);
+
+impl Write for HashWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.write(s.as_bytes());
+
+        Ok(())
+    }
+}
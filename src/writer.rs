@@ -1,6 +1,22 @@
+use chrono::{
+    format::{Fixed, Item, StrftimeItems},
+    Offset,
+};
 use log::Record;
+use std::{cell::RefCell, path::Path};
 
-use crate::Config;
+use crate::{
+    kv::{self, KeyValue},
+    Config,
+};
+
+/// Appended to the messages cut at `max_message_length`.
+pub(crate) const TRUNCATED_MARKER: &str = "…[truncated]";
+
+thread_local! {
+    /// The thread that logged the record a worker thread is writing on its behalf.
+    static LOGGING_THREAD: RefCell<Option<String>> = const { RefCell::new(None) };
+}
 
 pub(crate) struct LogWriter<'a> {
     record: &'a Record<'a>,
@@ -13,35 +29,203 @@ impl<'a> LogWriter<'a> {
     }
 
     pub fn get_datetime(&self) -> String {
-        #[cfg(not(feature = "timezone"))]
-        return chrono::Local::now()
-            .format(&self.config.datetime_format)
-            .to_string();
-
-        #[cfg(feature = "timezone")]
-        return chrono::Local::now()
-            .with_timezone(&self.config.timezone)
-            .format(&self.config.datetime_format)
-            .to_string();
+        format_datetime(self.config)
     }
 
     pub fn get_level(&self) -> String {
         self.record.level().to_string()
     }
 
+    /// The target of the record, or the `default_target` when the record has no explicit target,
+    /// i.e. its target is its module path.
     pub fn get_target(&self) -> String {
-        self.record.target().to_string()
+        match &self.config.default_target {
+            Some(target) if self.record.module_path() == Some(self.record.target()) => {
+                target.clone()
+            }
+            _ => self.record.target().to_string(),
+        }
+    }
+
+    /// The name of the crate that logged the record, the first segment of the module path.
+    pub fn get_crate(&self) -> String {
+        let path = self.record.module_path().unwrap_or(self.record.target());
+
+        path.split("::").next().unwrap_or_default().to_string()
     }
 
+    /// The formatter only accepts `str`s, so the message is always valid UTF-8. A `Display`
+    /// implementation returning an error would make `to_string` panic, so keep what it wrote instead.
+    /// A message longer than `max_message_length` characters is cut without formatting the rest.
     pub fn get_args(&self) -> String {
-        self.record.args().to_string()
+        let mut args = BoundedString {
+            value: String::new(),
+            remaining: self.config.max_message_length,
+            truncated: false,
+        };
+        let _ = std::fmt::write(&mut args, *self.record.args());
+
+        if args.truncated {
+            args.value.push_str(TRUNCATED_MARKER);
+        }
+
+        args.value
+    }
+
+    /// The structured key-values of the record, empty without the `kv` feature.
+    /// Cut at `max_key_values` and `max_kv_value_length`.
+    pub fn get_key_values(&self) -> Vec<KeyValue> {
+        kv::limit(kv::collect(self.record), self.config)
     }
 
+    /// The file of the record, relative to the `source_root` when it is inside of it.
     pub fn get_file(&self) -> Option<String> {
-        self.record.file().map(|f| f.to_string())
+        if !self.has_location() {
+            return None;
+        }
+
+        let file = self.record.file()?;
+
+        let relative = self
+            .config
+            .source_root
+            .as_ref()
+            .and_then(|root| Path::new(file).strip_prefix(root).ok());
+
+        match relative {
+            Some(relative) => Some(relative.to_string_lossy().to_string()),
+            None => Some(file.to_string()),
+        }
     }
 
     pub fn get_line(&self) -> Option<u32> {
+        if !self.has_location() {
+            return None;
+        }
+
         self.record.line()
     }
+
+    /// Whether the record is at or above the `location_min_level`.
+    fn has_location(&self) -> bool {
+        self.config
+            .location_min_level
+            .map_or(true, |level| self.record.level() <= level)
+    }
+
+    pub fn get_version(&self) -> Option<String> {
+        self.config.version.clone()
+    }
+
+    pub fn get_commit(&self) -> Option<String> {
+        self.config.commit.clone()
+    }
+
+    /// The id of the process with `show_pid`.
+    pub fn get_pid(&self) -> Option<u32> {
+        self.config.show_pid.then(std::process::id)
+    }
+
+    /// The name of the thread that logged the record with `show_thread`.
+    pub fn get_thread(&self) -> Option<String> {
+        if !self.config.show_thread {
+            return None;
+        }
+
+        LOGGING_THREAD
+            .with_borrow(|thread| thread.clone())
+            .or_else(|| Some(thread_name()))
+    }
+}
+
+/// The name of the current thread, or its id when it is unnamed, e.g. `ThreadId(2)`.
+pub(crate) fn thread_name() -> String {
+    let thread = std::thread::current();
+
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
+/// Run `f` on a worker thread as the thread that logged the record, so the records written by
+/// the worker show the thread they were logged on.
+pub(crate) fn as_logging_thread<R>(thread: &str, f: impl FnOnce() -> R) -> R {
+    let previous = LOGGING_THREAD.replace(Some(thread.to_string()));
+    let result = f();
+    LOGGING_THREAD.set(previous);
+
+    result
+}
+
+/// Format the current datetime with the format and timezone of the config.
+pub(crate) fn format_datetime(config: &Config) -> String {
+    #[cfg(not(feature = "timezone"))]
+    let now = chrono::Local::now();
+
+    #[cfg(feature = "timezone")]
+    let now = chrono::Local::now().with_timezone(&config.timezone);
+
+    let mut datetime = now.format(&config.datetime_format).to_string();
+
+    if config.utc_suffix
+        && now.offset().fix().local_minus_utc() == 0
+        && !has_offset(&config.datetime_format)
+    {
+        datetime.push('Z');
+    }
+
+    datetime
+}
+
+/// Whether the datetime format already prints the offset or the name of the timezone.
+fn has_offset(datetime_format: &str) -> bool {
+    StrftimeItems::new(datetime_format).any(|item| {
+        matches!(
+            item,
+            Item::Fixed(
+                Fixed::TimezoneName
+                    | Fixed::TimezoneOffset
+                    | Fixed::TimezoneOffsetZ
+                    | Fixed::TimezoneOffsetColon
+                    | Fixed::TimezoneOffsetColonZ
+                    | Fixed::TimezoneOffsetDoubleColon
+                    | Fixed::TimezoneOffsetTripleColon
+                    | Fixed::RFC2822
+                    | Fixed::RFC3339
+            )
+        )
+    })
+}
+
+/// A string that stops the formatting once it holds `remaining` more characters.
+struct BoundedString {
+    value: String,
+    remaining: Option<usize>,
+    truncated: bool,
+}
+
+impl std::fmt::Write for BoundedString {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let Some(remaining) = self.remaining else {
+            self.value.push_str(s);
+
+            return Ok(());
+        };
+
+        match s.char_indices().nth(remaining) {
+            Some((end, _)) => {
+                self.value.push_str(&s[..end]);
+                self.truncated = true;
+
+                Err(std::fmt::Error)
+            }
+            None => {
+                self.value.push_str(s);
+                self.remaining = Some(remaining - s.chars().count());
+
+                Ok(())
+            }
+        }
+    }
 }